@@ -0,0 +1,200 @@
+# Type stubs for the `backbone` extension module, built from src/python.rs.
+
+from typing import Any, Hashable, Literal, Mapping, Sequence, TypeAlias
+
+# (source, target, layer_start, layer_end, layer_weight_index, weight)
+TupleEdge: TypeAlias = tuple[int, int, int, int, int, float]
+# tuple edges, or a pair (edges, weights) of NumPy arrays as taken by backbone_array_py
+TupleEdges: TypeAlias = Sequence[TupleEdge] | tuple[Any, Any]
+# (source label, target label, layer_start, layer_end, layer_weight_index, weight)
+LabeledTupleEdge: TypeAlias = tuple[str, str, int, int, int, float]
+# (source, target, layer_start name, layer_end name, layer weight name, weight)
+NamedLayerTupleEdge: TypeAlias = tuple[int, int, str, str, str, float]
+# ((layer_start, layer_end), layer_weight_index)
+EdgeLayer: TypeAlias = tuple[tuple[int, int], int]
+NamedEdgeLayer: TypeAlias = tuple[tuple[str, str], str]
+# the weight of a distance in each layer it uses
+Distance: TypeAlias = dict[EdgeLayer, float]
+DistanceLike: TypeAlias = MultiDistance | Mapping[EdgeLayer, float]
+# closure[source][target] is the Pareto set of distances from source to target
+Closure: TypeAlias = dict[int, dict[int, list[Distance]]]
+Backbone: TypeAlias = dict[int, dict[int, list[Distance]]]
+BackboneAlgorithm: TypeAlias = Literal["simas", "costa", "costa-parallel", "naive"]
+DuplicatePolicy: TypeAlias = Literal["error", "first", "last", "min", "sum", "count"]
+
+class MultiDistance:
+    """A distance with one weight per layer, partially ordered by dominance."""
+
+    def __init__(self, weights: Mapping[EdgeLayer, float] | None = None) -> None: ...
+    @staticmethod
+    def from_tuple(
+        layer_start: int, layer_end: int, layer_weight_index: int, weight: float
+    ) -> MultiDistance: ...
+    @property
+    def weights(self) -> Distance: ...
+    def dominates(self, other: DistanceLike) -> bool: ...
+    def is_comparable(self, other: DistanceLike) -> bool: ...
+    def __add__(self, other: DistanceLike) -> MultiDistance: ...
+    def __lt__(self, other: DistanceLike) -> bool: ...
+    def __le__(self, other: DistanceLike) -> bool: ...
+    def __gt__(self, other: DistanceLike) -> bool: ...
+    def __ge__(self, other: DistanceLike) -> bool: ...
+    def __eq__(self, other: object) -> bool: ...
+    def __ne__(self, other: object) -> bool: ...
+
+class MultidistanceGraph:
+    """A directed graph with at most one edge per node pair."""
+
+    def __init__(self, edges: Sequence[TupleEdge] | None = None) -> None: ...
+    def add_edge(self, source: int, target: int, weight: DistanceLike) -> None: ...
+    def remove_edge(self, source: int, target: int) -> None: ...
+    def edge_weight(self, source: int, target: int) -> MultiDistance | None: ...
+    def nodes(self) -> list[int]: ...
+    def neighbors(self, node: int) -> list[tuple[int, MultiDistance]]: ...
+    def in_neighbors(self, node: int) -> list[tuple[int, MultiDistance]]: ...
+    def edges(self) -> list[tuple[int, int, MultiDistance]]: ...
+    def n_edges(self) -> int: ...
+    def __len__(self) -> int: ...
+    def copy(self) -> MultidistanceGraph: ...
+    def closure(self) -> Closure: ...
+    def shortest_distances(
+        self, source: int, n_steps: int | None = None
+    ) -> dict[int, list[MultiDistance]]: ...
+    def pareto_distances(self, source: int, target: int) -> list[MultiDistance]: ...
+    def is_metric(self, source: int, target: int, n_steps: int | None = None) -> bool: ...
+    def backbone(self, algorithm: BackboneAlgorithm = "costa-parallel") -> MultidistanceGraph: ...
+
+class BackboneResult:
+    """Every edge of an edge list with its classification."""
+
+    def edges(self) -> list[TupleEdge]: ...
+    def removed_edges(self) -> list[TupleEdge]: ...
+    def metric_mask(self) -> list[bool]: ...
+    def pareto_distances(self, source: int, target: int) -> list[MultiDistance]: ...
+    def closure(self) -> Closure: ...
+    def to_networkx(
+        self,
+        nodes: Sequence[Hashable] | None = None,
+        layers: Sequence[Hashable] | None = None,
+    ) -> Any: ...
+    def to_pandas(
+        self,
+        nodes: Sequence[Hashable] | None = None,
+        layers: Sequence[Hashable] | None = None,
+    ) -> Any: ...
+    def __len__(self) -> int: ...
+
+def backbone_result_py(edges: TupleEdges) -> BackboneResult: ...
+def pareto_distances_py(
+    edges: TupleEdges, source: int, target: int
+) -> list[MultiDistance]: ...
+def distance_closure_py(edges: TupleEdges) -> Closure: ...
+def backbone_py(edges: TupleEdges) -> Backbone: ...
+def distance_closure_labeled_py(
+    edges: Sequence[LabeledTupleEdge],
+) -> dict[str, dict[str, list[Distance]]]: ...
+def backbone_labeled_py(
+    edges: Sequence[LabeledTupleEdge],
+) -> dict[str, dict[str, list[Distance]]]: ...
+def distance_closure_named_py(
+    edges: Sequence[NamedLayerTupleEdge],
+) -> dict[int, dict[int, list[dict[NamedEdgeLayer, float]]]]: ...
+def backbone_named_py(
+    edges: Sequence[NamedLayerTupleEdge],
+) -> dict[int, dict[int, list[dict[NamedEdgeLayer, float]]]]: ...
+def distance_closure_undirected_py(edges: TupleEdges) -> Closure: ...
+def backbone_undirected_py(edges: TupleEdges) -> Backbone: ...
+def multigraph_backbone_py(edges: TupleEdges) -> list[tuple[int, int, Distance]]: ...
+def structural_backbone_simas(edges: TupleEdges) -> dict[int, dict[int, Distance]]: ...
+def structural_backbone_costa(edges: TupleEdges) -> dict[int, dict[int, Distance]]: ...
+def structural_backbone_costa_parallel(
+    edges: TupleEdges,
+) -> dict[int, dict[int, Distance]]: ...
+def structural_backbone_naive(edges: TupleEdges) -> dict[int, dict[int, Distance]]: ...
+def compare_backbones_py(edges: TupleEdges) -> dict[str, set[tuple[int, int]]]: ...
+
+# NumPy arrays: edges of shape (n, 5), weights of shape (n,)
+def backbone_array_py(edges: Any, weights: Any) -> tuple[Any, Any]: ...
+def metric_mask_array_py(edges: Any, weights: Any) -> Any: ...
+
+# networkx graphs
+def edges_from_networkx_py(
+    graph: Any, weight: str = "weight", layer: str = "layer"
+) -> tuple[list[TupleEdge], list[Hashable], list[Hashable]]: ...
+def backbone_networkx_py(
+    graph: Any,
+    weight: str = "weight",
+    layer: str = "layer",
+    pareto: str | None = "pareto",
+) -> Any: ...
+
+# preparing edge lists
+def merge_duplicate_edges_py(
+    edges: TupleEdges, policy: DuplicatePolicy = "last"
+) -> tuple[list[TupleEdge], dict[str, Any]]: ...
+def coupled_edge_list_py(
+    layers: Sequence[Sequence[tuple[int, int, float]]],
+    identity_edge_weight: float | None = None,
+    layer_pair_weights: Mapping[tuple[int, int], float] | None = None,
+    node_weights: Mapping[int, float] | None = None,
+) -> tuple[list[TupleEdge], list[tuple[int, int]]]: ...
+def flatten_backbone_py(
+    edges: TupleEdges,
+    state_nodes: Sequence[tuple[int, int]],
+    selection: Literal["union", "intersection"] = "union",
+    aggregation: Literal["sum", "min", "max", "count"] = "min",
+) -> list[tuple[int, int, float]]: ...
+
+# files
+def read_edge_list_py(
+    path: str,
+    delimiter: str | None = None,
+    source: int = 0,
+    target: int = 1,
+    layer: int | None = None,
+    layer_end: int | None = None,
+    layer_name: str = "0",
+    weight: int | None = None,
+    comment: str | None = "#",
+    header: bool = False,
+    duplicates: DuplicatePolicy = "last",
+) -> tuple[list[TupleEdge], list[str], list[str], dict[str, Any]]: ...
+def save_graph_py(
+    path: str, edges: TupleEdges, layers: Sequence[str] | None = None
+) -> None: ...
+def load_graph_py(path: str) -> tuple[list[tuple[int, int, Distance]], list[str]]: ...
+def save_closure_py(
+    path: str, closure: Closure, layers: Sequence[str] | None = None
+) -> None: ...
+def load_closure_py(path: str) -> tuple[Closure, list[str]]: ...
+def save_backbone_py(
+    path: str, backbone: Backbone, layers: Sequence[str] | None = None
+) -> None: ...
+def load_backbone_py(path: str) -> tuple[Backbone, list[str]]: ...
+
+# only with the `graphml` feature
+def read_graphml_py(
+    path: str,
+    weight: str = "distance",
+    layer: str | None = None,
+    layer_end: str | None = None,
+    node_layer: str | None = None,
+    layer_name: str = "0",
+) -> tuple[list[TupleEdge], list[str], list[str]]: ...
+def write_backbone_graphml_py(
+    path: str,
+    edges: TupleEdges,
+    nodes: Sequence[str] | None = None,
+    layers: Sequence[str] | None = None,
+) -> None: ...
+
+# only with the `arrow` and `parquet` features
+def distance_closure_arrow_py(
+    edges: TupleEdges, layers: Sequence[str] | None = None
+) -> Any: ...
+def write_closure_ipc_py(
+    path: str, edges: TupleEdges, layers: Sequence[str] | None = None
+) -> None: ...
+def write_closure_parquet_py(
+    path: str, edges: TupleEdges, layers: Sequence[str] | None = None
+) -> None: ...
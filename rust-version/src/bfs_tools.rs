@@ -1,6 +1,6 @@
 use crate::{
     multidistance::{MultiDistance, NodeID},
-    parteto_shortest_distance_from_source, MultidistanceGraph,
+    pareto_distances_bidirectional, parteto_shortest_distance_from_source, MultidistanceGraph,
 };
 use std::collections::HashMap;
 pub type EdgeMap<S> = HashMap<NodeID, Vec<(NodeID, MultiDistance)>, S>;
@@ -26,16 +26,41 @@ pub fn is_metric_in_n_steps(
 ) -> Result<bool, MissingEdgeError> {
     let neighbors = graph.neighbor_edges(&source);
     if let Some((_, test_edge_weight)) = neighbors.iter().find(|(node, _)| node == &target) {
-        let shortest_dists = parteto_shortest_distance_from_source(
-            source,
-            graph,
-            n_steps,
-            Some((&target, &test_edge_weight.clone())),
-        );
+        return is_edge_metric_in_n_steps(graph, source, target, test_edge_weight, n_steps);
+    }
+
+    Err(MissingEdgeError { source, target })
+}
 
-        if let Some(dist_to_target) = shortest_dists.get(&target) {
-            return Ok(dist_to_target.iter().any(|md| test_edge_weight == md));
+/// Same as [`is_metric_in_n_steps`], but tests the edge from `source` to
+/// `target` with the given weight. This distinguishes between parallel edges
+/// of a multigraph; the edge itself is assumed to exist.
+///
+/// The graph is searched from both ends (see [`pareto_distances_bidirectional`]).
+///
+/// # Errors
+/// `MissingEdgeError` is returned if `target` cannot be reached from
+/// `source`.
+pub fn is_edge_metric_in_n_steps(
+    graph: &impl MultidistanceGraph,
+    source: NodeID,
+    target: NodeID,
+    weight: &MultiDistance,
+    n_steps: Option<usize>,
+) -> Result<bool, MissingEdgeError> {
+    if source != target {
+        let dists = pareto_distances_bidirectional(graph, source, target, n_steps, Some(weight));
+        if dists.is_empty() {
+            return Err(MissingEdgeError { source, target });
         }
+        return Ok(dists.contains(weight));
+    }
+
+    let shortest_dists =
+        parteto_shortest_distance_from_source(source, graph, n_steps, Some((&target, weight)));
+
+    if let Some(dist_to_target) = shortest_dists.get(&target) {
+        return Ok(dist_to_target.iter().any(|md| weight == md));
     }
 
     Err(MissingEdgeError { source, target })
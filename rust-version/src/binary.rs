@@ -0,0 +1,460 @@
+use crate::closure::MultidistanceClosure;
+use crate::direct_backbone::MultilayerBackbone;
+use crate::layer_registry::LayerRegistry;
+use crate::multidistance::{EdgeLayerID, MultiDistance, NodeID};
+use crate::multigraph::MultidistanceGraph;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+const MAGIC: &[u8; 4] = b"MLBB";
+
+/// Version of the binary format for caching graphs, closures and backbones
+/// on disk. Files with a higher version are rejected.
+///
+/// Every file starts with a header
+///
+/// * the magic bytes `MLBB`,
+/// * the format version (`u16`),
+/// * the kind of content (`u8`, see [`ContentKind`]),
+/// * the layer names and layer weight names (`u32` count, then one string
+///   each),
+///
+/// followed by the content. Integers and floats are little-endian, node ids
+/// are `u64`, layer indices `u32` and strings are a `u32` byte length followed
+/// by UTF-8. A `MultiDistance` is a `u32` entry count followed by
+/// `(layer_start, layer_end, layer_weight_index, weight)` for every entry.
+///
+/// A graph is stored as a `u64` node count followed by the node ids, then a
+/// `u64` edge count followed by `(source, target, distance)` for every edge.
+/// Closures and backbones are stored as a
+/// `u64` source count; every source is its id and a `u64` target count, every
+/// target its id, a `u32` distance count and the distances.
+pub const FORMAT_VERSION: u16 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContentKind {
+    Graph,
+    Closure,
+    Backbone,
+}
+
+impl ContentKind {
+    fn tag(self) -> u8 {
+        match self {
+            ContentKind::Graph => 0,
+            ContentKind::Closure => 1,
+            ContentKind::Backbone => 2,
+        }
+    }
+
+    fn from_tag(tag: u8) -> Option<ContentKind> {
+        match tag {
+            0 => Some(ContentKind::Graph),
+            1 => Some(ContentKind::Closure),
+            2 => Some(ContentKind::Backbone),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ContentKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContentKind::Graph => write!(f, "graph"),
+            ContentKind::Closure => write!(f, "closure"),
+            ContentKind::Backbone => write!(f, "backbone"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum BinaryFormatError {
+    Io(io::Error),
+    /// The input does not start with the magic bytes.
+    NotBackboneFile,
+    UnsupportedVersion(u16),
+    UnknownContentKind(u8),
+    WrongContentKind {
+        expected: ContentKind,
+        found: ContentKind,
+    },
+    InvalidString,
+    /// A count or id does not fit into `usize` or the header fields.
+    TooLarge,
+}
+
+impl fmt::Display for BinaryFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BinaryFormatError::Io(e) => write!(f, "{e}"),
+            BinaryFormatError::NotBackboneFile => write!(f, "not a backbone file"),
+            BinaryFormatError::UnsupportedVersion(version) => write!(
+                f,
+                "unsupported format version {version}, expected 1 to {FORMAT_VERSION}"
+            ),
+            BinaryFormatError::UnknownContentKind(tag) => write!(f, "unknown content kind {tag}"),
+            BinaryFormatError::WrongContentKind { expected, found } => {
+                write!(f, "expected a {expected}, found a {found}")
+            }
+            BinaryFormatError::InvalidString => write!(f, "string is not valid UTF-8"),
+            BinaryFormatError::TooLarge => write!(f, "value too large for the format"),
+        }
+    }
+}
+
+impl std::error::Error for BinaryFormatError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BinaryFormatError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for BinaryFormatError {
+    fn from(e: io::Error) -> Self {
+        BinaryFormatError::Io(e)
+    }
+}
+
+/// Writes every node and edge of `graph`.
+///
+/// # Errors
+/// Returns a `BinaryFormatError` if writing fails.
+pub fn write_graph(
+    mut writer: impl Write,
+    graph: &impl MultidistanceGraph,
+    layers: &LayerRegistry,
+) -> Result<(), BinaryFormatError> {
+    write_header(&mut writer, ContentKind::Graph, layers)?;
+    let mut nodes = graph.nodes();
+    nodes.sort_unstable();
+    write_len_u64(&mut writer, nodes.len())?;
+    for node in &nodes {
+        write_len_u64(&mut writer, node.0)?;
+    }
+
+    let edges: Vec<(NodeID, NodeID, MultiDistance)> = nodes
+        .into_iter()
+        .flat_map(|source| {
+            graph
+                .neighbor_edges(&source)
+                .into_iter()
+                .map(move |(target, weight)| (source, target, weight))
+        })
+        .collect();
+
+    write_len_u64(&mut writer, edges.len())?;
+    for (source, target, weight) in &edges {
+        write_len_u64(&mut writer, source.0)?;
+        write_len_u64(&mut writer, target.0)?;
+        write_distance(&mut writer, weight)?;
+    }
+    Ok(())
+}
+
+/// Reads a graph written by [`write_graph`] into any graph type.
+///
+/// # Errors
+/// Returns a `BinaryFormatError` if reading fails or the input is not a graph.
+pub fn read_graph<T>(mut reader: impl Read) -> Result<(T, LayerRegistry), BinaryFormatError>
+where
+    T: MultidistanceGraph + Default,
+{
+    let layers = read_header(&mut reader, ContentKind::Graph)?;
+    let mut graph = T::default();
+    for _ in 0..read_len_u64(&mut reader)? {
+        graph.add_node(NodeID(read_len_u64(&mut reader)?));
+    }
+    for _ in 0..read_len_u64(&mut reader)? {
+        let source = NodeID(read_len_u64(&mut reader)?);
+        let target = NodeID(read_len_u64(&mut reader)?);
+        graph.add_edge(source, target, read_distance(&mut reader)?);
+    }
+    Ok((graph, layers))
+}
+
+/// # Errors
+/// Returns a `BinaryFormatError` if writing fails.
+pub fn write_closure(
+    writer: impl Write,
+    closure: &MultidistanceClosure,
+    layers: &LayerRegistry,
+) -> Result<(), BinaryFormatError> {
+    write_distance_map(writer, ContentKind::Closure, closure, layers)
+}
+
+/// # Errors
+/// Returns a `BinaryFormatError` if reading fails or the input is not a
+/// closure.
+pub fn read_closure(
+    reader: impl Read,
+) -> Result<(MultidistanceClosure, LayerRegistry), BinaryFormatError> {
+    read_distance_map(reader, ContentKind::Closure)
+}
+
+/// # Errors
+/// Returns a `BinaryFormatError` if writing fails.
+pub fn write_backbone(
+    writer: impl Write,
+    backbone: &MultilayerBackbone,
+    layers: &LayerRegistry,
+) -> Result<(), BinaryFormatError> {
+    write_distance_map(writer, ContentKind::Backbone, backbone, layers)
+}
+
+/// # Errors
+/// Returns a `BinaryFormatError` if reading fails or the input is not a
+/// backbone.
+pub fn read_backbone(
+    reader: impl Read,
+) -> Result<(MultilayerBackbone, LayerRegistry), BinaryFormatError> {
+    read_distance_map(reader, ContentKind::Backbone)
+}
+
+fn write_distance_map(
+    mut writer: impl Write,
+    kind: ContentKind,
+    map: &HashMap<NodeID, HashMap<NodeID, Vec<MultiDistance>>>,
+    layers: &LayerRegistry,
+) -> Result<(), BinaryFormatError> {
+    write_header(&mut writer, kind, layers)?;
+    write_len_u64(&mut writer, map.len())?;
+    for (source, targets) in map {
+        write_len_u64(&mut writer, source.0)?;
+        write_len_u64(&mut writer, targets.len())?;
+        for (target, distances) in targets {
+            write_len_u64(&mut writer, target.0)?;
+            write_len_u32(&mut writer, distances.len())?;
+            for distance in distances {
+                write_distance(&mut writer, distance)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[allow(clippy::type_complexity)]
+fn read_distance_map(
+    mut reader: impl Read,
+    kind: ContentKind,
+) -> Result<
+    (
+        HashMap<NodeID, HashMap<NodeID, Vec<MultiDistance>>>,
+        LayerRegistry,
+    ),
+    BinaryFormatError,
+> {
+    let layers = read_header(&mut reader, kind)?;
+    let mut map = HashMap::new();
+    for _ in 0..read_len_u64(&mut reader)? {
+        let source = NodeID(read_len_u64(&mut reader)?);
+        let mut targets = HashMap::new();
+        for _ in 0..read_len_u64(&mut reader)? {
+            let target = NodeID(read_len_u64(&mut reader)?);
+            // no preallocation, since the count may be corrupt
+            let mut distances = Vec::new();
+            for _ in 0..read_len_u32(&mut reader)? {
+                distances.push(read_distance(&mut reader)?);
+            }
+            targets.insert(target, distances);
+        }
+        map.insert(source, targets);
+    }
+    Ok((map, layers))
+}
+
+fn write_header(
+    writer: &mut impl Write,
+    kind: ContentKind,
+    layers: &LayerRegistry,
+) -> Result<(), BinaryFormatError> {
+    writer.write_all(MAGIC)?;
+    writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+    writer.write_all(&[kind.tag()])?;
+
+    let layer_names: Vec<&str> = (0..layers.n_layers())
+        .filter_map(|i| layers.layer_name(i))
+        .collect();
+    let weight_names: Vec<&str> = (0..layers.n_layer_weights())
+        .filter_map(|i| layers.layer_weight_name(i))
+        .collect();
+    for names in [layer_names, weight_names] {
+        write_len_u32(writer, names.len())?;
+        for name in names {
+            write_len_u32(writer, name.len())?;
+            writer.write_all(name.as_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+/// Reads the header and returns the layers.
+fn read_header(
+    reader: &mut impl Read,
+    expected: ContentKind,
+) -> Result<LayerRegistry, BinaryFormatError> {
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(BinaryFormatError::NotBackboneFile);
+    }
+    let mut version = [0; 2];
+    reader.read_exact(&mut version)?;
+    let version = u16::from_le_bytes(version);
+    if version == 0 || version > FORMAT_VERSION {
+        return Err(BinaryFormatError::UnsupportedVersion(version));
+    }
+    let mut tag = [0; 1];
+    reader.read_exact(&mut tag)?;
+    let found =
+        ContentKind::from_tag(tag[0]).ok_or(BinaryFormatError::UnknownContentKind(tag[0]))?;
+    if found != expected {
+        return Err(BinaryFormatError::WrongContentKind { expected, found });
+    }
+
+    let mut layers = LayerRegistry::new();
+    for _ in 0..read_len_u32(reader)? {
+        layers.add_layer(&read_string(reader)?);
+    }
+    for _ in 0..read_len_u32(reader)? {
+        layers.add_layer_weight(&read_string(reader)?);
+    }
+    Ok(layers)
+}
+
+fn write_distance(
+    writer: &mut impl Write,
+    distance: &MultiDistance,
+) -> Result<(), BinaryFormatError> {
+    write_len_u32(writer, distance.total.len())?;
+    for (layer, weight) in &distance.total {
+        write_len_u32(writer, layer.layer_start)?;
+        write_len_u32(writer, layer.layer_end)?;
+        write_len_u32(writer, layer.layer_weight_index)?;
+        writer.write_all(&weight.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+fn read_distance(reader: &mut impl Read) -> Result<MultiDistance, BinaryFormatError> {
+    let mut distance = MultiDistance::default();
+    for _ in 0..read_len_u32(reader)? {
+        let layer = EdgeLayerID {
+            layer_start: read_len_u32(reader)?,
+            layer_end: read_len_u32(reader)?,
+            layer_weight_index: read_len_u32(reader)?,
+        };
+        let mut weight = [0; 4];
+        reader.read_exact(&mut weight)?;
+        distance.total.insert(layer, f32::from_le_bytes(weight));
+    }
+    Ok(distance)
+}
+
+fn read_string(reader: &mut impl Read) -> Result<String, BinaryFormatError> {
+    let len = read_len_u32(reader)?;
+    // read what is there instead of allocating whatever length a corrupt
+    // file claims
+    let mut bytes = Vec::new();
+    reader.take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != len {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+    String::from_utf8(bytes).map_err(|_| BinaryFormatError::InvalidString)
+}
+
+fn write_len_u32(writer: &mut impl Write, value: usize) -> Result<(), BinaryFormatError> {
+    let value = u32::try_from(value).map_err(|_| BinaryFormatError::TooLarge)?;
+    writer.write_all(&value.to_le_bytes())?;
+    Ok(())
+}
+
+fn write_len_u64(writer: &mut impl Write, value: usize) -> Result<(), BinaryFormatError> {
+    let value = u64::try_from(value).map_err(|_| BinaryFormatError::TooLarge)?;
+    writer.write_all(&value.to_le_bytes())?;
+    Ok(())
+}
+
+fn read_len_u32(reader: &mut impl Read) -> Result<usize, BinaryFormatError> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    usize::try_from(u32::from_le_bytes(bytes)).map_err(|_| BinaryFormatError::TooLarge)
+}
+
+fn read_len_u64(reader: &mut impl Read) -> Result<usize, BinaryFormatError> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    usize::try_from(u64::from_le_bytes(bytes)).map_err(|_| BinaryFormatError::TooLarge)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{multidistance_closure, multilayer_backbone, MultidistanceGraphHashmap};
+
+    #[test]
+    fn test_round_trip() {
+        let edges = [
+            (0, 1, 0, 0, 0, 1.0),
+            (1, 2, 0, 1, 0, 1.5),
+            (0, 2, 1, 1, 1, 3.0),
+            (2, 0, 1, 0, 0, 0.0),
+        ];
+        let layers = LayerRegistry::from_layer_names(&["calls", "mobility"]);
+        let mut graph = MultidistanceGraphHashmap::from_tuple_edge_list(&edges);
+        graph.add_node(NodeID(7));
+        let closure = multidistance_closure(&graph);
+        let backbone = multilayer_backbone(&edges);
+
+        let mut buffer = Vec::new();
+        write_graph(&mut buffer, &graph, &layers).unwrap();
+        let (read, read_layers): (MultidistanceGraphHashmap, _) =
+            read_graph(buffer.as_slice()).unwrap();
+        assert!(read == graph);
+        assert_eq!(read_layers, layers);
+        assert_eq!(read.nodes().len(), 4);
+
+        let mut buffer = Vec::new();
+        write_closure(&mut buffer, &closure, &layers).unwrap();
+        assert_eq!(read_closure(buffer.as_slice()).unwrap().0, closure);
+        assert!(matches!(
+            read_backbone(buffer.as_slice()),
+            Err(BinaryFormatError::WrongContentKind {
+                expected: ContentKind::Backbone,
+                found: ContentKind::Closure
+            })
+        ));
+
+        let mut buffer = Vec::new();
+        write_backbone(&mut buffer, &backbone, &layers).unwrap();
+        assert_eq!(read_backbone(buffer.as_slice()).unwrap().0, backbone);
+
+        for version in [0, 0xff] {
+            buffer[4] = version;
+            assert!(matches!(
+                read_backbone(buffer.as_slice()),
+                Err(BinaryFormatError::UnsupportedVersion(v)) if v == u16::from(version)
+            ));
+        }
+        assert!(matches!(
+            read_backbone(&b"PK\x03\x04"[..]),
+            Err(BinaryFormatError::NotBackboneFile)
+        ));
+    }
+
+    #[test]
+    fn test_corrupt_lengths() {
+        let layers = LayerRegistry::from_layer_names(&["calls"]);
+        let mut buffer = Vec::new();
+        write_closure(&mut buffer, &MultidistanceClosure::new(), &layers).unwrap();
+
+        // the length of the first layer name, right after the layer count
+        buffer[11..15].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            read_closure(buffer.as_slice()),
+            Err(BinaryFormatError::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof
+        ));
+    }
+}
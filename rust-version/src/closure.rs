@@ -1,10 +1,10 @@
 use crate::{
-    multidistance::{MultiDistance, NodeID},
+    multidistance::{EdgeLayerID, MultiDistance, NodeID},
     shortest_paths::parteto_shortest_distance_from_source,
     MultidistanceGraph,
 };
 use rayon::prelude::*;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 #[allow(clippy::module_name_repetitions)]
 pub type MultidistanceClosure = HashMap<NodeID, HashMap<NodeID, Vec<MultiDistance>>>;
 
@@ -28,10 +28,79 @@ pub fn multidistance_closure(graph: &(impl MultidistanceGraph + Sync)) -> Multid
         })
 }
 
+/// The upper triangle of the closure of an undirected graph. Distances are
+/// symmetric, so only the entries `closure[u][v]` with `u < v` are kept, which
+/// halves the memory of the closure. This is a memory saving only: every node
+/// but the largest (which has no such entries) is still searched from in
+/// full, so the search work is that of [`multidistance_closure`].
+#[must_use]
+pub fn multidistance_closure_upper_triangle(
+    graph: &(impl MultidistanceGraph + Sync),
+) -> MultidistanceClosure {
+    let nodes = graph.nodes();
+    let Some(last) = nodes.iter().max().copied() else {
+        return HashMap::new();
+    };
+    nodes
+        .par_iter()
+        .filter(|source| **source != last)
+        .map(|source| {
+            let mut pareto_dists =
+                parteto_shortest_distance_from_source(*source, graph, None, None);
+            pareto_dists.retain(|target, _| target > source);
+            (*source, pareto_dists)
+        })
+        .collect()
+}
+
+/// Lays out a closure as a table: the layers used by any of its distances,
+/// sorted by `EdgeLayerID`, and one `(source, target, pareto_index, distance)`
+/// row per Pareto distance, sorted by node pair. `pareto_index` is the
+/// position of the distance in the Pareto set of the pair.
+///
+/// The rows are produced lazily, sorting the targets of one source at a time,
+/// so that large closures can be written out without a copy of every row.
+#[allow(clippy::type_complexity)]
+pub fn closure_table(
+    closure: &MultidistanceClosure,
+) -> (
+    Vec<EdgeLayerID>,
+    impl Iterator<Item = (NodeID, NodeID, usize, &MultiDistance)>,
+) {
+    let layers = closure
+        .values()
+        .flat_map(HashMap::values)
+        .flatten()
+        .flat_map(|distance| distance.total.keys())
+        .map(|l| (l.layer_start, l.layer_end, l.layer_weight_index))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(|(layer_start, layer_end, layer_weight_index)| EdgeLayerID {
+            layer_start,
+            layer_end,
+            layer_weight_index,
+        })
+        .collect();
+
+    let mut sources: Vec<_> = closure.iter().collect();
+    sources.sort_unstable_by_key(|(source, _)| **source);
+    let rows = sources.into_iter().flat_map(|(source, targets)| {
+        let mut targets: Vec<_> = targets.iter().collect();
+        targets.sort_unstable_by_key(|(target, _)| **target);
+        targets.into_iter().flat_map(move |(target, distances)| {
+            distances
+                .iter()
+                .enumerate()
+                .map(move |(i, distance)| (*source, *target, i, distance))
+        })
+    });
+    (layers, rows)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::MultidistanceGraphHashmap;
+    use crate::{MultidistanceGraphHashmap, UndirectedMultidistanceGraphHashmap};
     #[allow(clippy::redundant_clone)]
     #[test]
     fn test_simple_multilayer_closure() {
@@ -74,4 +143,43 @@ mod tests {
 
         assert_eq!(expected, closure);
     }
+
+    #[test]
+    fn test_undirected_closure_is_upper_triangle() {
+        let edges = [
+            (0, 1, 0, 0, 0, 1.0),
+            (1, 2, 0, 1, 0, 1.0),
+            (2, 3, 1, 1, 0, 1.0),
+            (3, 0, 0, 1, 0, 2.0),
+        ];
+        let undirected = UndirectedMultidistanceGraphHashmap::from_tuple_edge_list(&edges);
+        let symmetrized = MultidistanceGraphHashmap::from_tuple_edge_list(
+            &edges
+                .iter()
+                .flat_map(|&(u, v, l1, l2, i, w)| [(u, v, l1, l2, i, w), (v, u, l1, l2, i, w)])
+                .collect::<Vec<_>>(),
+        );
+
+        let directed = multidistance_closure(&symmetrized);
+        let upper = multidistance_closure_upper_triangle(&undirected);
+
+        for (source, targets) in &directed {
+            for (target, dists) in targets {
+                let stored = upper.get(source).and_then(|t| t.get(target));
+                if source < target {
+                    // Pareto sets are unordered
+                    let stored = stored.unwrap();
+                    assert_eq!(stored.len(), dists.len());
+                    assert!(dists.iter().all(|d| stored.contains(d)));
+                } else {
+                    assert!(stored.is_none());
+                }
+            }
+        }
+        assert_eq!(
+            upper[&NodeID(0)][&NodeID(3)].len(),
+            2,
+            "the direct edge and the path through both layers are incomparable"
+        );
+    }
 }
@@ -0,0 +1,258 @@
+use crate::closure::{closure_table, MultidistanceClosure};
+use crate::layer_registry::LayerRegistry;
+use crate::multidistance::{EdgeLayerID, MultiDistance, NodeID};
+use arrow_array::{
+    ArrayRef, Float32Array, RecordBatch, RecordBatchReader, UInt32Array, UInt64Array,
+};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::sync::Arc;
+
+/// Number of rows per batch that [`write_closure_ipc`] and
+/// [`write_closure_parquet`] write.
+pub const CLOSURE_BATCH_SIZE: usize = 1 << 16;
+
+/// The record batches of a closure, see [`closure_record_batches`].
+pub struct ClosureBatches<'a> {
+    schema: SchemaRef,
+    layer_ids: Vec<EdgeLayerID>,
+    rows: Box<dyn Iterator<Item = (NodeID, NodeID, usize, &'a MultiDistance)> + 'a>,
+    batch_size: usize,
+}
+
+impl Iterator for ClosureBatches<'_> {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut sources = Vec::new();
+        let mut targets = Vec::new();
+        let mut pareto_indices = Vec::new();
+        let mut weights = vec![Vec::new(); self.layer_ids.len()];
+        for (source, target, i, distance) in self.rows.by_ref().take(self.batch_size) {
+            let Ok(i) = u32::try_from(i) else {
+                return Some(Err(ArrowError::ComputeError("Pareto set too large".into())));
+            };
+            sources.push(source.0 as u64);
+            targets.push(target.0 as u64);
+            pareto_indices.push(i);
+            for (column, layer) in weights.iter_mut().zip(&self.layer_ids) {
+                column.push(distance.total.get(layer).copied().unwrap_or(0.0));
+            }
+        }
+        if sources.is_empty() {
+            return None;
+        }
+
+        let mut columns: Vec<ArrayRef> = vec![
+            Arc::new(UInt64Array::from(sources)),
+            Arc::new(UInt64Array::from(targets)),
+            Arc::new(UInt32Array::from(pareto_indices)),
+        ];
+        columns.extend(
+            weights
+                .into_iter()
+                .map(|column| Arc::new(Float32Array::from(column)) as ArrayRef),
+        );
+        Some(RecordBatch::try_new(self.schema.clone(), columns))
+    }
+}
+
+impl RecordBatchReader for ClosureBatches<'_> {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+/// Flattens a closure into one row per Pareto distance, with the columns
+///
+/// * `source`, `target` (`UInt64`): the node pair,
+/// * `pareto_index` (`UInt32`): the position of the distance in the Pareto
+///   set of the pair,
+/// * one `Float32` column per `EdgeLayerID` in the closure, named by
+///   [`LayerRegistry::edge_layer_label`], holding the weight of the distance
+///   in that layer (zero if it does not use the layer).
+///
+/// Rows and columns follow [`closure_table`]. The rows are emitted in batches
+/// of at most `batch_size` rows, which are only built when asked for.
+///
+/// # Errors
+/// Returns an `ArrowError` if the schema cannot be assembled, e.g. because two
+/// layers have the same column name. Every batch fails if a Pareto set has more
+/// than `u32::MAX` distances.
+pub fn closure_record_batches<'a>(
+    closure: &'a MultidistanceClosure,
+    layers: &LayerRegistry,
+    batch_size: usize,
+) -> Result<ClosureBatches<'a>, ArrowError> {
+    let (layer_ids, rows) = closure_table(closure);
+    let mut fields = vec![
+        Field::new("source", DataType::UInt64, false),
+        Field::new("target", DataType::UInt64, false),
+        Field::new("pareto_index", DataType::UInt32, false),
+    ];
+    fields.extend(
+        layer_ids
+            .iter()
+            .map(|layer| Field::new(layers.edge_layer_label(layer), DataType::Float32, false)),
+    );
+    let schema = Schema::new(fields);
+    if let Some(name) = duplicate_field_name(&schema) {
+        return Err(ArrowError::SchemaError(format!(
+            "two layers are named {name:?}"
+        )));
+    }
+    Ok(ClosureBatches {
+        schema: Arc::new(schema),
+        layer_ids,
+        rows: Box::new(rows),
+        batch_size: batch_size.max(1),
+    })
+}
+
+/// Same as [`closure_record_batches`], but in a single batch, e.g. to hand a
+/// small closure over to another library in one piece.
+///
+/// # Errors
+/// See [`closure_record_batches`].
+pub fn closure_record_batch(
+    closure: &MultidistanceClosure,
+    layers: &LayerRegistry,
+) -> Result<RecordBatch, ArrowError> {
+    let mut batches = closure_record_batches(closure, layers, usize::MAX)?;
+    let schema = batches.schema();
+    batches
+        .next()
+        .unwrap_or_else(|| Ok(RecordBatch::new_empty(schema)))
+}
+
+fn duplicate_field_name(schema: &Schema) -> Option<&str> {
+    let mut seen = BTreeSet::new();
+    schema
+        .fields()
+        .iter()
+        .map(|f| f.name().as_str())
+        .find(|name| !seen.insert(*name))
+}
+
+/// Writes [`closure_record_batches`] as an Arrow IPC (Feather v2) file, one
+/// batch of [`CLOSURE_BATCH_SIZE`] rows at a time.
+///
+/// # Errors
+/// Returns an `ArrowError` if a batch cannot be built or written.
+pub fn write_closure_ipc(
+    writer: impl Write,
+    closure: &MultidistanceClosure,
+    layers: &LayerRegistry,
+) -> Result<(), ArrowError> {
+    let batches = closure_record_batches(closure, layers, CLOSURE_BATCH_SIZE)?;
+    let mut writer = arrow_ipc::writer::FileWriter::try_new(writer, &batches.schema())?;
+    for batch in batches {
+        writer.write(&batch?)?;
+    }
+    writer.finish()
+}
+
+/// Writes [`closure_record_batches`] as a Parquet file, one batch of
+/// [`CLOSURE_BATCH_SIZE`] rows at a time.
+///
+/// # Errors
+/// Returns a `ParquetError` if a batch cannot be built or written.
+#[cfg(feature = "parquet")]
+pub fn write_closure_parquet(
+    writer: impl Write + Send,
+    closure: &MultidistanceClosure,
+    layers: &LayerRegistry,
+) -> Result<(), parquet::errors::ParquetError> {
+    let batches = closure_record_batches(closure, layers, CLOSURE_BATCH_SIZE)?;
+    let mut writer = parquet::arrow::ArrowWriter::try_new(writer, batches.schema(), None)?;
+    for batch in batches {
+        writer.write(&batch?)?;
+    }
+    writer.close()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distance_closure;
+    use arrow_array::Array;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_closure_record_batch() {
+        // two incomparable paths from 0 to 2
+        let closure = distance_closure(&[
+            (0, 1, 0, 0, 0, 1.0),
+            (1, 2, 0, 0, 0, 1.0),
+            (0, 2, 1, 1, 0, 1.0),
+        ]);
+        let layers = LayerRegistry::from_layer_names(&["calls", "mobility"]);
+        let batch = closure_record_batch(&closure, &layers).unwrap();
+
+        let names: Vec<&str> = batch
+            .schema_ref()
+            .fields()
+            .iter()
+            .map(|f| f.name().as_str())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "source",
+                "target",
+                "pareto_index",
+                "calls->calls:weight",
+                "mobility->mobility:weight"
+            ]
+        );
+        let n_labels: usize = closure
+            .values()
+            .flat_map(HashMap::values)
+            .map(Vec::len)
+            .sum();
+        assert_eq!(batch.num_rows(), n_labels);
+
+        let column = |i: usize| batch.column(i).as_any();
+        let targets = column(1).downcast_ref::<UInt64Array>().unwrap();
+        let calls = column(3).downcast_ref::<Float32Array>().unwrap();
+        let mobility = column(4).downcast_ref::<Float32Array>().unwrap();
+        let to_2: Vec<(f32, f32)> = (0..batch.num_rows())
+            .filter(|&row| column(0).downcast_ref::<UInt64Array>().unwrap().value(row) == 0)
+            .filter(|&row| targets.value(row) == 2)
+            .map(|row| (calls.value(row), mobility.value(row)))
+            .collect();
+        assert_eq!(to_2.len(), 2);
+        assert!(to_2.contains(&(2.0, 0.0)) && to_2.contains(&(0.0, 1.0)));
+        assert_eq!(calls.null_count(), 0);
+
+        let mut file = Vec::new();
+        write_closure_ipc(&mut file, &closure, &layers).unwrap();
+        let read: Vec<RecordBatch> =
+            arrow_ipc::reader::FileReader::try_new(std::io::Cursor::new(file), None)
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+        assert_eq!(read, vec![batch.clone()]);
+
+        let batches: Vec<RecordBatch> = closure_record_batches(&closure, &layers, 2)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(batches.len(), n_labels.div_ceil(2));
+        let mut offset = 0;
+        for small in &batches {
+            assert_eq!(*small, batch.slice(offset, small.num_rows()));
+            offset += small.num_rows();
+        }
+        assert_eq!(offset, n_labels);
+
+        #[cfg(feature = "parquet")]
+        {
+            let mut file = Vec::new();
+            write_closure_parquet(&mut file, &closure, &layers).unwrap();
+            assert!(file.starts_with(b"PAR1") && file.ends_with(b"PAR1"));
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use crate::{
-    is_metric_in_n_steps,
+    is_edge_metric_in_n_steps,
     multidistance::{MultiDistance, NodeID},
     multimin, parteto_shortest_distance_from_source, MultidistanceGraph,
 };
@@ -21,12 +21,47 @@ where
             if distances.get(&target).is_some_and(|distances_to_target| {
                 distances_to_target.iter().any(|d| d < &direct_weight)
             }) {
-                graph.remove_edge(source, target);
+                graph.remove_edge_with_weight(source, target, &direct_weight);
             }
         }
     }
 }
 
+/// Parallel variant of [`fast_backbone_costa`].
+///
+/// Every source is searched concurrently against the unmodified graph and the
+/// semimetric edges are removed afterwards. Removing an edge that is strictly
+/// dominated by some path never changes a Pareto distance, so the result is
+/// the same as removing edges while iterating.
+pub fn fast_backbone_costa_parallel<T>(graph: &mut T)
+where
+    T: MultidistanceGraph + Sync,
+{
+    let snapshot: &T = graph;
+    let semimetric_edges: Vec<(NodeID, NodeID, MultiDistance)> = snapshot
+        .nodes()
+        .par_iter()
+        .flat_map(|source| -> Vec<(NodeID, NodeID, MultiDistance)> {
+            let distances = parteto_shortest_distance_from_source(*source, snapshot, None, None);
+
+            snapshot
+                .neighbor_edges(source)
+                .into_iter()
+                .filter(|(target, direct_weight)| {
+                    distances.get(target).is_some_and(|distances_to_target| {
+                        distances_to_target.iter().any(|d| d < direct_weight)
+                    })
+                })
+                .map(|(target, weight)| (*source, target, weight))
+                .collect()
+        })
+        .collect();
+
+    for (u, v, weight) in &semimetric_edges {
+        graph.remove_edge_with_weight(*u, *v, weight);
+    }
+}
+
 pub fn fast_backbone_simas<T>(graph: &mut T)
 where
     T: MultidistanceGraph + Sync,
@@ -37,24 +72,25 @@ where
     let mut known_metric_edges = one_step_metric_edges(graph);
     two_step_metric_edges(graph, &mut known_metric_edges); // modifies `known_metric_edges` in-place
 
-    let semimetric_edges: Vec<(NodeID, NodeID)> = graph
+    let semimetric_edges: Vec<(NodeID, NodeID, MultiDistance)> = graph
         .nodes()
         .par_iter()
-        .flat_map(|source| -> Vec<(NodeID, NodeID)> {
+        .flat_map(|source| -> Vec<(NodeID, NodeID, MultiDistance)> {
             graph
                 .neighbor_edges(source)
-                .par_iter()
-                .filter(|(target, _)| {
+                .into_par_iter()
+                .filter(|(target, weight)| {
                     !known_metric_edges.contains(&(*source, *target))
-                        && !is_metric_in_n_steps(graph, *source, *target, None).unwrap_or(false)
+                        && !is_edge_metric_in_n_steps(graph, *source, *target, weight, None)
+                            .unwrap_or(false)
                 })
-                .map(|(target, _)| (*source, *target))
+                .map(|(target, weight)| (*source, target, weight))
                 .collect()
         })
         .collect();
 
-    for (u, v) in &semimetric_edges {
-        graph.remove_edge(*u, *v);
+    for (u, v, weight) in &semimetric_edges {
+        graph.remove_edge_with_weight(*u, *v, weight);
     }
 }
 
@@ -64,32 +100,77 @@ pub fn structural_backbone<T>(
 ) where
     T: MultidistanceGraph + Sync,
 {
-    let semimetric_edges: Vec<(NodeID, NodeID)> = graph
+    let semimetric_edges: Vec<(NodeID, NodeID, MultiDistance)> = graph
         .nodes()
         .par_iter()
-        .flat_map(|source| -> Vec<(NodeID, NodeID)> {
+        .flat_map(|source| -> Vec<(NodeID, NodeID, MultiDistance)> {
             graph
                 .neighbor_edges(source)
-                .par_iter()
-                .filter(|(target, _)| {
-                    !is_metric_in_n_steps(graph, *source, *target, n_steps).unwrap_or(false)
+                .into_par_iter()
+                .filter(|(target, weight)| {
+                    !is_edge_metric_in_n_steps(graph, *source, *target, weight, n_steps)
+                        .unwrap_or(false)
                 })
-                .map(|(target, _)| (*source, *target))
+                .map(|(target, weight)| (*source, target, weight))
                 .collect()
         })
         .collect();
 
-    for (u, v) in &semimetric_edges {
-        graph.remove_edge(*u, *v);
+    for (u, v, weight) in &semimetric_edges {
+        graph.remove_edge_with_weight(*u, *v, weight);
     }
 }
 
+/// Neighbors of a node, excluding the node pairs that carry parallel edges.
+/// The heuristics below reason about node pairs, which is only valid when the
+/// pair has a single edge; parallel edges are always checked by a search.
+fn single_edge_targets(neighbors: &[(NodeID, MultiDistance)]) -> HashSet<NodeID> {
+    let mut seen = HashSet::new();
+    let mut repeated = HashSet::new();
+    for (target, _) in neighbors {
+        if !seen.insert(*target) {
+            repeated.insert(*target);
+        }
+    }
+    seen.retain(|target| !repeated.contains(target));
+    seen
+}
+
+/// Edges that are minimal among the out-edges of their source or among the
+/// in-edges of their target. Every path between the endpoints starts with an
+/// out-edge of the source and ends with an in-edge of the target, so no path
+/// can be shorter than such an edge.
 fn one_step_metric_edges<T>(graph: &T) -> HashSet<(NodeID, NodeID)>
 where
     T: MultidistanceGraph + Sync,
 {
-    // TODO: incorporate edge reversal for min incoming edges
-    min_edges_with_condition(graph, |_, _, _| true)
+    let mut metric_edges = min_edges_with_condition(graph, |_, _, _| true);
+    metric_edges.extend(min_in_edges(graph));
+    metric_edges
+}
+
+fn min_in_edges<T>(graph: &T) -> HashSet<(NodeID, NodeID)>
+where
+    T: MultidistanceGraph + Sync,
+{
+    graph
+        .nodes()
+        .par_iter()
+        .flat_map_iter(|target| {
+            let in_neighbors = graph.in_neighbor_edges(target);
+            let in_weights: Vec<MultiDistance> =
+                in_neighbors.iter().map(|(_, dist)| dist.clone()).collect();
+            let multimin_for_target = multimin(&in_weights);
+            let single_edge = single_edge_targets(&in_neighbors);
+
+            in_neighbors
+                .into_iter()
+                .filter(move |(source, md)| {
+                    single_edge.contains(source) && multimin_for_target.contains(md)
+                })
+                .map(move |(source, _)| (source, *target))
+        })
+        .collect()
 }
 
 fn min_edges_with_condition<T>(
@@ -103,20 +184,22 @@ where
         .nodes()
         .iter()
         .flat_map(|source| -> HashSet<(NodeID, NodeID)> {
-            let out_edges: Vec<MultiDistance> = graph
-                .neighbor_edges(source)
+            let neighbors = graph.neighbor_edges(source);
+            let out_edges: Vec<MultiDistance> = neighbors
                 .iter()
                 .filter(|(t, d)| condition(source, t, d))
                 .map(|(_, dist)| dist.clone())
                 .collect();
 
             let multimin_for_source = multimin(&out_edges);
+            let single_edge = single_edge_targets(&neighbors);
 
             HashSet::from_par_iter(
-                graph
-                    .neighbor_edges(source)
+                neighbors
                     .par_iter()
-                    .filter(|(_, md)| multimin_for_source.contains(md))
+                    .filter(|(target, md)| {
+                        single_edge.contains(target) && multimin_for_source.contains(md)
+                    })
                     .map(|(target, _)| (*source, *target)),
             )
         })
@@ -128,17 +211,11 @@ where
     T: MultidistanceGraph + Sync,
 {
     for source in &graph.nodes() {
-        let mut remainder: HashMap<NodeID, MultiDistance> = graph
-            .neighbor_edges(source)
-            .clone()
-            .into_iter()
-            .collect::<HashMap<_, _>>();
+        let mut remainder: Vec<(NodeID, MultiDistance)> = graph.neighbor_edges(source);
+        let single_edge = single_edge_targets(&remainder);
         let mut two_hop_known_metric_dists = Vec::new();
-        for (s, target) in known_metric_edges.iter() {
-            if s != source {
-                continue;
-            }
-            if let Some(dist) = remainder.get(target) {
+        for (target, dist) in &remainder {
+            if known_metric_edges.contains(&(*source, *target)) {
                 for (_, dist2) in graph.neighbor_edges(target) {
                     two_hop_known_metric_dists.push(dist.clone() + dist2.clone());
                 }
@@ -148,23 +225,354 @@ where
         loop {
             let mut continue_search = false;
 
-            remainder.retain(|target, _| !known_metric_edges.contains(&(*source, *target)));
-            let remainder_weights: Vec<MultiDistance> = remainder.values().cloned().collect();
+            remainder.retain(|(target, _)| !known_metric_edges.contains(&(*source, *target)));
+            let remainder_weights: Vec<MultiDistance> =
+                remainder.iter().map(|(_, md)| md.clone()).collect();
             let min_weights = multimin(&remainder_weights);
 
+            let mut new_two_hop_dists = Vec::new();
             for (target, multidist) in &remainder {
-                if min_weights.contains(multidist)
-                    && !two_hop_known_metric_dists
+                if single_edge.contains(target)
+                    && min_weights.contains(multidist)
+                    && two_hop_known_metric_dists
                         .iter()
                         .all(|d2| d2.not_less_than(multidist))
                 {
                     known_metric_edges.insert((*source, *target));
+                    // paths through this edge are no longer bounded by the
+                    // remaining out-edges, so track them like the others
+                    for (_, dist2) in graph.neighbor_edges(target) {
+                        new_two_hop_dists.push(multidist.clone() + dist2);
+                    }
                     continue_search = true;
                 }
             }
+            two_hop_known_metric_dists.append(&mut new_two_hop_dists);
             if !continue_search {
                 break;
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        multidistance_closure, multilayer_backbone, multilayer_backbone_undirected,
+        MultidistanceGraphHashmap, MultidistanceMultigraphHashmap,
+    };
+    use proptest::prelude::*;
+
+    type TupleEdge = (usize, usize, usize, usize, usize, f32);
+
+    /// Random multilayer edge lists without self-loops or repeated node pairs.
+    /// Weights are drawn from a small set so that ties and zero weights are
+    /// common.
+    fn multilayer_edge_list() -> impl Strategy<Value = Vec<TupleEdge>> {
+        random_edge_list().prop_map(|edges| {
+            let mut seen = HashSet::new();
+            edges
+                .into_iter()
+                .filter(|(u, v, ..)| seen.insert((*u, *v)))
+                .collect()
+        })
+    }
+
+    /// Same as [`multilayer_edge_list`], but a node pair may be connected by
+    /// one edge per pair of layers.
+    fn parallel_edge_list() -> impl Strategy<Value = Vec<TupleEdge>> {
+        random_edge_list().prop_map(|edges| {
+            let mut seen = HashSet::new();
+            edges
+                .into_iter()
+                .filter(|(u, v, l1, l2, ..)| seen.insert((*u, *v, *l1, *l2)))
+                .collect()
+        })
+    }
+
+    fn random_edge_list() -> impl Strategy<Value = Vec<TupleEdge>> {
+        (2..8_usize, 1..4_usize, 0.0..1.0_f64).prop_flat_map(|(n_nodes, n_layers, p_inter)| {
+            let weight = prop::sample::select(vec![0.0_f32, 1.0, 1.0, 2.0, 3.0, 4.5]);
+            let edge = (
+                0..n_nodes,
+                0..n_nodes,
+                0..n_layers,
+                0..n_layers,
+                0.0..1.0_f64,
+                weight,
+            )
+                .prop_map(move |(u, v, l1, l2, coin, w)| {
+                    let layer_end = if coin < p_inter { l2 } else { l1 };
+                    (u, v, l1, layer_end, 0, w)
+                });
+            prop::collection::vec(edge, 0..4 * n_nodes)
+                .prop_map(|edges| edges.into_iter().filter(|(u, v, ..)| u != v).collect())
+        })
+    }
+
+    fn edge_set(graph: &MultidistanceGraphHashmap) -> HashSet<(NodeID, NodeID)> {
+        graph
+            .nodes()
+            .iter()
+            .flat_map(|u| graph.neighbor_edges(u).into_iter().map(|(v, _)| (*u, v)))
+            .collect()
+    }
+
+    fn backbone_edge_set(backbone: &MultilayerBackbone) -> HashSet<(NodeID, NodeID)> {
+        backbone
+            .iter()
+            .flat_map(|(u, targets)| targets.keys().map(|v| (*u, *v)))
+            .collect()
+    }
+
+    /// A hashable form of a `MultiDistance`.
+    type WeightKey = Vec<(usize, usize, usize, u32)>;
+
+    fn weighted_edge_set(
+        graph: &MultidistanceMultigraphHashmap,
+    ) -> HashSet<(NodeID, NodeID, WeightKey)> {
+        graph
+            .edge_list()
+            .map(|(u, v, weight)| {
+                let mut layers: Vec<_> = weight
+                    .total
+                    .iter()
+                    .map(|(l, w)| {
+                        (
+                            l.layer_start,
+                            l.layer_end,
+                            l.layer_weight_index,
+                            w.to_bits(),
+                        )
+                    })
+                    .collect();
+                layers.sort_unstable();
+                (u, v, layers)
+            })
+            .collect()
+    }
+
+    /// Every edge as `(source, target, weight)`, read from the in-edges.
+    fn reversed_edge_set(
+        graph: &MultidistanceMultigraphHashmap,
+    ) -> HashSet<(NodeID, NodeID, WeightKey)> {
+        let mut reversed = MultidistanceMultigraphHashmap::new();
+        for target in graph.nodes() {
+            for (source, weight) in graph.in_neighbor_edges(&target) {
+                reversed.add_edge(source, target, weight);
+            }
+        }
+        weighted_edge_set(&reversed)
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(512))]
+
+        #[test]
+        fn test_backbone_algorithms_agree(edges in multilayer_edge_list()) {
+            let graph = MultidistanceGraphHashmap::from_tuple_edge_list(&edges);
+
+            let mut costa = graph.clone();
+            fast_backbone_costa(&mut costa);
+            let mut costa_parallel = graph.clone();
+            fast_backbone_costa_parallel(&mut costa_parallel);
+            let mut simas = graph.clone();
+            fast_backbone_simas(&mut simas);
+            let mut naive = graph.clone();
+            structural_backbone(&mut naive, None);
+            let from_closure = backbone_edge_set(&multilayer_backbone(&edges));
+
+            let expected = edge_set(&naive);
+            prop_assert_eq!(&edge_set(&costa), &expected);
+            prop_assert_eq!(&edge_set(&costa_parallel), &expected);
+            prop_assert_eq!(&edge_set(&simas), &expected);
+            prop_assert_eq!(&from_closure, &expected);
+        }
+
+        #[test]
+        fn test_parallel_edges_are_classified_separately(edges in parallel_edge_list()) {
+            let graph = MultidistanceMultigraphHashmap::from_tuple_edge_list(&edges);
+            let closure = multidistance_closure(&graph);
+
+            let mut from_closure = graph.clone();
+            for (u, v, weight) in graph.edge_list() {
+                if !closure[&u][&v].contains(weight) {
+                    from_closure.remove_edge_with_weight(u, v, weight);
+                }
+            }
+
+            let mut costa = graph.clone();
+            fast_backbone_costa(&mut costa);
+            let mut costa_parallel = graph.clone();
+            fast_backbone_costa_parallel(&mut costa_parallel);
+            let mut simas = graph.clone();
+            fast_backbone_simas(&mut simas);
+            let mut naive = graph.clone();
+            structural_backbone(&mut naive, None);
+
+            let expected = weighted_edge_set(&from_closure);
+            prop_assert_eq!(&weighted_edge_set(&costa), &expected);
+            prop_assert_eq!(&weighted_edge_set(&costa_parallel), &expected);
+            prop_assert_eq!(&weighted_edge_set(&simas), &expected);
+            prop_assert_eq!(&weighted_edge_set(&naive), &expected);
+            prop_assert_eq!(&reversed_edge_set(&simas), &expected);
+        }
+
+        #[test]
+        fn test_undirected_backbone_matches_symmetrized(edges in multilayer_edge_list()) {
+            let mut seen = HashSet::new();
+            let edges: Vec<TupleEdge> = edges
+                .into_iter()
+                .filter(|(u, v, ..)| seen.insert((*u.min(v), *u.max(v))))
+                .collect();
+            let symmetrized: Vec<TupleEdge> = edges
+                .iter()
+                .flat_map(|&(u, v, l1, l2, i, w)| [(u, v, l1, l2, i, w), (v, u, l1, l2, i, w)])
+                .collect();
+
+            let directed = backbone_edge_set(&multilayer_backbone(&symmetrized));
+            let undirected = backbone_edge_set(&multilayer_backbone_undirected(&edges));
+
+            prop_assert!(undirected.iter().all(|(u, v)| u < v));
+            prop_assert_eq!(
+                undirected,
+                directed.into_iter().filter(|(u, v)| u < v).collect::<HashSet<_>>()
+            );
+        }
+
+        #[test]
+        fn test_backbone_is_idempotent(edges in multilayer_edge_list()) {
+            let mut graph = MultidistanceGraphHashmap::from_tuple_edge_list(&edges);
+            structural_backbone(&mut graph, None);
+            let once = edge_set(&graph);
+            structural_backbone(&mut graph, None);
+
+            prop_assert_eq!(edge_set(&graph), once);
+        }
+    }
+
+    #[test]
+    fn test_parallel_edges() {
+        // calls (layer 0) and mobility (layer 1) both connect 0 and 1; the
+        // mobility edge 0 -> 1 is beaten by the mobility path 0 -> 2 -> 1
+        let edges = [
+            (0, 1, 0, 0, 0, 2.0),
+            (0, 1, 1, 1, 0, 5.0),
+            (0, 2, 1, 1, 0, 1.0),
+            (2, 1, 1, 1, 0, 1.0),
+        ];
+        let mut graph = MultidistanceMultigraphHashmap::from_tuple_edge_list(&edges);
+        assert_eq!(graph.parallel_edges(NodeID(0), NodeID(1)).len(), 2);
+
+        fast_backbone_simas(&mut graph);
+        assert_eq!(
+            graph.parallel_edges(NodeID(0), NodeID(1)),
+            &[MultiDistance::from_tuple(0, 0, 0, 2.0)]
+        );
+
+        let backbone = crate::multigraph_backbone(&edges);
+        assert_eq!(backbone.len(), 3);
+        assert!(!backbone.contains(&(
+            NodeID(0),
+            NodeID(1),
+            MultiDistance::from_tuple(1, 1, 0, 5.0)
+        )));
+    }
+
+    #[test]
+    fn test_one_step_metric_edges_include_min_in_edges() {
+        // 0 -> 2 is not the shortest out-edge of 0, but the shortest in-edge of 2
+        let graph = MultidistanceGraphHashmap::from_tuple_edge_list(&[
+            (0, 1, 0, 0, 0, 1.0),
+            (0, 2, 0, 0, 0, 2.0),
+            (1, 2, 0, 0, 0, 3.0),
+            (1, 3, 0, 0, 0, 1.0),
+        ]);
+        assert_eq!(graph.in_neighbor_edges(&NodeID(2)).len(), 2);
+
+        assert_eq!(
+            one_step_metric_edges(&graph),
+            HashSet::from([
+                (NodeID(0), NodeID(1)),
+                (NodeID(0), NodeID(2)),
+                (NodeID(1), NodeID(3))
+            ])
+        );
+    }
+
+    #[test]
+    fn test_in_neighbor_edges_follow_removed_parallel_edges() {
+        let mut graph = MultidistanceMultigraphHashmap::from_tuple_edge_list(&[
+            (0, 1, 0, 0, 0, 1.0),
+            (0, 1, 1, 1, 0, 2.0),
+        ]);
+        let layer_1 = MultiDistance::from_tuple(1, 1, 0, 2.0);
+        assert_eq!(graph.in_neighbor_edges(&NodeID(1)).len(), 2);
+
+        graph.remove_edge_with_weight(
+            NodeID(0),
+            NodeID(1),
+            &MultiDistance::from_tuple(0, 0, 0, 1.0),
+        );
+        assert_eq!(
+            graph.in_neighbor_edges(&NodeID(1)),
+            vec![(NodeID(0), layer_1.clone())]
+        );
+
+        graph.remove_edge_with_weight(NodeID(0), NodeID(1), &layer_1);
+        assert!(graph.in_neighbor_edges(&NodeID(1)).is_empty());
+    }
+
+    #[test]
+    fn test_two_step_metric_edges_skip_edges_beaten_by_two_hops() {
+        // 0 -> 1 -> 2 is shorter than 0 -> 2, which is the only other out-edge of 0
+        let edges = [
+            (0, 1, 0, 0, 0, 1.0),
+            (1, 2, 0, 0, 0, 1.0),
+            (0, 2, 0, 0, 0, 3.0),
+        ];
+        let mut graph = MultidistanceGraphHashmap::from_tuple_edge_list(&edges);
+
+        let mut known_metric_edges = one_step_metric_edges(&graph);
+        two_step_metric_edges(&graph, &mut known_metric_edges);
+        assert!(!known_metric_edges.contains(&(NodeID(0), NodeID(2))));
+
+        fast_backbone_simas(&mut graph);
+        assert_eq!(graph.edge_weight(NodeID(0), NodeID(2)), None);
+        assert_eq!(
+            edge_set(&graph),
+            HashSet::from([(NodeID(0), NodeID(1)), (NodeID(1), NodeID(2))])
+        );
+    }
+
+    #[test]
+    fn test_parallel_costa_matches_sequential_and_naive() {
+        let graph = MultidistanceGraphHashmap::from_tuple_edge_list(&[
+            (0, 1, 0, 0, 0, 1.0),
+            (0, 2, 0, 0, 0, 3.0),
+            (0, 3, 0, 1, 0, 2.0),
+            (1, 2, 0, 0, 0, 1.0),
+            (1, 4, 0, 1, 0, 1.0),
+            (2, 0, 0, 0, 0, 1.0),
+            (2, 3, 0, 1, 0, 1.0),
+            (4, 3, 1, 1, 0, 1.0),
+            (3, 5, 1, 1, 0, 2.0),
+            (4, 5, 1, 1, 0, 4.0),
+            (5, 0, 1, 0, 0, 0.5),
+        ]);
+
+        let mut sequential = graph.clone();
+        fast_backbone_costa(&mut sequential);
+
+        let mut parallel = graph.clone();
+        fast_backbone_costa_parallel(&mut parallel);
+
+        let mut naive = graph.clone();
+        structural_backbone(&mut naive, None);
+
+        assert!(parallel != graph);
+        assert!(parallel == sequential);
+        assert!(parallel == naive);
+    }
+}
@@ -0,0 +1,223 @@
+use crate::multigraph::MultidistanceMultigraphHashmap;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::str::FromStr;
+
+/// Identifies an edge of a tuple edge list: source node, target node, source
+/// layer, target layer and layer weight index. Two rows with the same key are
+/// duplicates.
+pub type EdgeKey = (usize, usize, usize, usize, usize);
+
+/// What to do with rows of a tuple edge list that share an `EdgeKey`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum DuplicatePolicy {
+    /// Refuse edge lists with duplicates.
+    Error,
+    KeepFirst,
+    /// Keep the last row.
+    #[default]
+    KeepLast,
+    Min,
+    Sum,
+    /// Replace the weight by the number of rows, e.g. to count repeated hires.
+    Count,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDuplicatePolicyError(pub String);
+
+impl fmt::Display for ParseDuplicatePolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown duplicate policy {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseDuplicatePolicyError {}
+
+impl FromStr for DuplicatePolicy {
+    type Err = ParseDuplicatePolicyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(DuplicatePolicy::Error),
+            "first" => Ok(DuplicatePolicy::KeepFirst),
+            "last" => Ok(DuplicatePolicy::KeepLast),
+            "min" => Ok(DuplicatePolicy::Min),
+            "sum" => Ok(DuplicatePolicy::Sum),
+            "count" => Ok(DuplicatePolicy::Count),
+            _ => Err(ParseDuplicatePolicyError(s.to_string())),
+        }
+    }
+}
+
+/// Returned by `DuplicatePolicy::Error` for the first repeated edge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateEdgeError(pub EdgeKey);
+
+impl fmt::Display for DuplicateEdgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (source, target, layer_start, layer_end, layer_weight_index) = self.0;
+        write!(
+            f,
+            "duplicate edge {source} -> {target} \
+             (layers {layer_start} -> {layer_end}, layer weight {layer_weight_index})"
+        )
+    }
+}
+
+impl std::error::Error for DuplicateEdgeError {}
+
+/// What `merge_duplicate_edges` did.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DuplicateSummary {
+    /// Number of rows in the input.
+    pub n_input_edges: usize,
+    /// Number of rows after merging.
+    pub n_output_edges: usize,
+    /// Number of rows of every edge that appeared more than once.
+    pub duplicates: BTreeMap<EdgeKey, usize>,
+}
+
+impl DuplicateSummary {
+    /// Number of rows that were merged into another row.
+    #[must_use]
+    pub fn n_merged(&self) -> usize {
+        self.n_input_edges - self.n_output_edges
+    }
+}
+
+/// Merges the rows of `edges` that share an `EdgeKey` according to `policy`.
+/// Every edge is reported at the position of its first row.
+///
+/// # Errors
+/// Returns a `DuplicateEdgeError` for the first repeated edge if `policy` is
+/// `DuplicatePolicy::Error`.
+#[allow(clippy::type_complexity)]
+pub fn merge_duplicate_edges(
+    edges: &[(usize, usize, usize, usize, usize, f32)],
+    policy: DuplicatePolicy,
+) -> Result<
+    (
+        Vec<(usize, usize, usize, usize, usize, f32)>,
+        DuplicateSummary,
+    ),
+    DuplicateEdgeError,
+> {
+    let mut position: HashMap<EdgeKey, usize> = HashMap::new();
+    let mut merged: Vec<(usize, usize, usize, usize, usize, f32)> = Vec::new();
+    let mut counts: Vec<usize> = Vec::new();
+
+    for &(source, target, layer_start, layer_end, layer_weight_index, weight) in edges {
+        let key = (source, target, layer_start, layer_end, layer_weight_index);
+        let Some(&i) = position.get(&key) else {
+            position.insert(key, merged.len());
+            merged.push((
+                source,
+                target,
+                layer_start,
+                layer_end,
+                layer_weight_index,
+                weight,
+            ));
+            counts.push(1);
+            continue;
+        };
+
+        counts[i] += 1;
+        let kept = &mut merged[i].5;
+        match policy {
+            DuplicatePolicy::Error => return Err(DuplicateEdgeError(key)),
+            DuplicatePolicy::KeepFirst | DuplicatePolicy::Count => {}
+            DuplicatePolicy::KeepLast => *kept = weight,
+            DuplicatePolicy::Min => *kept = kept.min(weight),
+            DuplicatePolicy::Sum => *kept += weight,
+        }
+    }
+
+    if policy == DuplicatePolicy::Count {
+        for (edge, count) in merged.iter_mut().zip(&counts) {
+            #[allow(clippy::cast_precision_loss)] // counts stay far below 2^24
+            let count = *count as f32;
+            edge.5 = count;
+        }
+    }
+
+    let summary = DuplicateSummary {
+        n_input_edges: edges.len(),
+        n_output_edges: merged.len(),
+        duplicates: merged
+            .iter()
+            .zip(&counts)
+            .filter(|(_, count)| **count > 1)
+            .map(|(e, count)| ((e.0, e.1, e.2, e.3, e.4), *count))
+            .collect(),
+    };
+    Ok((merged, summary))
+}
+
+impl MultidistanceMultigraphHashmap {
+    /// Same as [`MultidistanceMultigraphHashmap::from_tuple_edge_list`], but
+    /// repeated edges are merged according to `policy`. Edges between the
+    /// same nodes in different layers are not duplicates, and are kept as
+    /// parallel edges.
+    ///
+    /// # Errors
+    /// See [`merge_duplicate_edges`].
+    pub fn from_tuple_edge_list_with_policy(
+        edges: &[(usize, usize, usize, usize, usize, f32)],
+        policy: DuplicatePolicy,
+    ) -> Result<(MultidistanceMultigraphHashmap, DuplicateSummary), DuplicateEdgeError> {
+        let (edges, summary) = merge_duplicate_edges(edges, policy)?;
+        Ok((
+            MultidistanceMultigraphHashmap::from_tuple_edge_list(&edges),
+            summary,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MultiDistance, NodeID};
+
+    #[test]
+    fn test_duplicate_policies() {
+        let edges = [
+            (0, 1, 0, 0, 0, 2.0),
+            (1, 2, 0, 0, 0, 1.0),
+            (0, 1, 0, 0, 0, 1.0),
+            (0, 1, 1, 1, 0, 4.0),
+            (0, 1, 0, 0, 0, 3.0),
+        ];
+        let weights = |policy| {
+            let (merged, _) = merge_duplicate_edges(&edges, policy).unwrap();
+            merged.iter().map(|e| e.5).collect::<Vec<_>>()
+        };
+
+        assert_eq!(weights(DuplicatePolicy::KeepFirst), vec![2.0, 1.0, 4.0]);
+        assert_eq!(weights(DuplicatePolicy::KeepLast), vec![3.0, 1.0, 4.0]);
+        assert_eq!(weights(DuplicatePolicy::Min), vec![1.0, 1.0, 4.0]);
+        assert_eq!(weights(DuplicatePolicy::Sum), vec![6.0, 1.0, 4.0]);
+        assert_eq!(weights(DuplicatePolicy::Count), vec![3.0, 1.0, 1.0]);
+        assert_eq!(
+            merge_duplicate_edges(&edges, DuplicatePolicy::Error),
+            Err(DuplicateEdgeError((0, 1, 0, 0, 0)))
+        );
+
+        let (graph, summary) = MultidistanceMultigraphHashmap::from_tuple_edge_list_with_policy(
+            &edges,
+            "sum".parse().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(summary.n_merged(), 2);
+        assert_eq!(summary.duplicates, BTreeMap::from([((0, 1, 0, 0, 0), 3)]));
+        // layers 0 and 1 are distinct edges
+        assert_eq!(
+            graph.parallel_edges(NodeID(0), NodeID(1)),
+            &[
+                MultiDistance::from_tuple(0, 0, 0, 6.0),
+                MultiDistance::from_tuple(1, 1, 0, 4.0)
+            ]
+        );
+    }
+}
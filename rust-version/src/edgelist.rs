@@ -0,0 +1,411 @@
+use crate::duplicates::{
+    merge_duplicate_edges, DuplicateEdgeError, DuplicatePolicy, DuplicateSummary,
+};
+use crate::layer_registry::LayerRegistry;
+use crate::multigraph::MultidistanceMultigraphHashmap;
+use crate::node_registry::NodeRegistry;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+/// Where the layers of an edge come from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LayerColumns {
+    /// Every edge lies in the named layer, e.g. when each layer is a file.
+    Fixed(String),
+    /// The layer name is in this column; edges are intralayer.
+    Single(usize),
+    /// The source and target layer names are in these columns.
+    Pair(usize, usize),
+}
+
+/// Layout of a delimited edge list. Columns are numbered from zero.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EdgeListFormat {
+    /// Field separator; `None` splits on any run of whitespace.
+    pub delimiter: Option<char>,
+    pub source_column: usize,
+    pub target_column: usize,
+    pub layers: LayerColumns,
+    /// Column of the edge weight; without one every row has weight 1.
+    pub weight_column: Option<usize>,
+    /// Name of the layer weight that the weights are stored under.
+    pub layer_weight: String,
+    /// Lines starting with this prefix are skipped.
+    pub comment_prefix: Option<String>,
+    /// Whether the first line that is not a comment holds column names.
+    pub has_header: bool,
+    /// What to do with repeated rows, e.g. `Count` for one row per hire.
+    pub duplicates: DuplicatePolicy,
+}
+
+impl Default for EdgeListFormat {
+    fn default() -> Self {
+        EdgeListFormat {
+            delimiter: None,
+            source_column: 0,
+            target_column: 1,
+            layers: LayerColumns::Fixed("0".to_string()),
+            weight_column: None,
+            layer_weight: "weight".to_string(),
+            comment_prefix: Some("#".to_string()),
+            has_header: false,
+            duplicates: DuplicatePolicy::default(),
+        }
+    }
+}
+
+impl EdgeListFormat {
+    /// Comma separated values with a header row.
+    #[must_use]
+    pub fn csv() -> EdgeListFormat {
+        EdgeListFormat {
+            delimiter: Some(','),
+            has_header: true,
+            ..EdgeListFormat::default()
+        }
+    }
+
+    /// Tab separated values without a header row.
+    #[must_use]
+    pub fn tsv() -> EdgeListFormat {
+        EdgeListFormat {
+            delimiter: Some('\t'),
+            ..EdgeListFormat::default()
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum EdgeListError {
+    Io(io::Error),
+    /// A row (1-based line number) has no field at `column`.
+    MissingColumn {
+        line: usize,
+        column: usize,
+    },
+    /// A weight is not a finite, non-negative number.
+    InvalidWeight {
+        line: usize,
+        value: String,
+    },
+    Duplicate(DuplicateEdgeError),
+}
+
+impl fmt::Display for EdgeListError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EdgeListError::Io(e) => write!(f, "{e}"),
+            EdgeListError::MissingColumn { line, column } => {
+                write!(f, "line {line}: no column {column}")
+            }
+            EdgeListError::InvalidWeight { line, value } => {
+                write!(f, "line {line}: invalid weight {value:?}")
+            }
+            EdgeListError::Duplicate(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for EdgeListError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EdgeListError::Io(e) => Some(e),
+            EdgeListError::Duplicate(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for EdgeListError {
+    fn from(e: io::Error) -> Self {
+        EdgeListError::Io(e)
+    }
+}
+
+impl From<DuplicateEdgeError> for EdgeListError {
+    fn from(e: DuplicateEdgeError) -> Self {
+        EdgeListError::Duplicate(e)
+    }
+}
+
+/// Parses a delimited edge list into the tuple format used by the rest of the
+/// crate. Node labels and layer names are interned in `nodes` and `layers`,
+/// so several files (e.g. one per layer) can share the same registries.
+/// Repeated rows are merged according to `format.duplicates`; the returned
+/// `DuplicateSummary` tells how many were.
+///
+/// # Errors
+/// Returns an `EdgeListError` if reading fails, a row is malformed, or
+/// `format.duplicates` is `DuplicatePolicy::Error` and a row is repeated.
+#[allow(clippy::type_complexity)]
+pub fn read_edge_list(
+    reader: impl BufRead,
+    format: &EdgeListFormat,
+    nodes: &mut NodeRegistry<String>,
+    layers: &mut LayerRegistry,
+) -> Result<
+    (
+        Vec<(usize, usize, usize, usize, usize, f32)>,
+        DuplicateSummary,
+    ),
+    EdgeListError,
+> {
+    let layer_weight_index = layers.add_layer_weight(&format.layer_weight);
+    let mut edges = Vec::new();
+    let mut header_pending = format.has_header;
+
+    for (i, line) in reader.lines().enumerate() {
+        let line_number = i + 1;
+        let line = line?;
+        let trimmed = line.trim();
+        if trimmed.is_empty()
+            || format
+                .comment_prefix
+                .as_deref()
+                .is_some_and(|prefix| trimmed.starts_with(prefix))
+        {
+            continue;
+        }
+        if header_pending {
+            header_pending = false;
+            continue;
+        }
+
+        let fields: Vec<&str> = match format.delimiter {
+            Some(delimiter) => line.split(delimiter).map(str::trim).collect(),
+            None => line.split_whitespace().collect(),
+        };
+        let field = |column: usize| {
+            fields
+                .get(column)
+                .copied()
+                .ok_or(EdgeListError::MissingColumn {
+                    line: line_number,
+                    column,
+                })
+        };
+
+        let source = nodes.get_or_insert(field(format.source_column)?.to_string());
+        let target = nodes.get_or_insert(field(format.target_column)?.to_string());
+        let (layer_start, layer_end) = match &format.layers {
+            LayerColumns::Fixed(name) => {
+                let layer = layers.add_layer(name);
+                (layer, layer)
+            }
+            LayerColumns::Single(column) => {
+                let layer = layers.add_layer(field(*column)?);
+                (layer, layer)
+            }
+            LayerColumns::Pair(start, end) => (
+                layers.add_layer(field(*start)?),
+                layers.add_layer(field(*end)?),
+            ),
+        };
+        let weight = match format.weight_column {
+            Some(column) => {
+                let value = field(column)?;
+                // every search assumes non-negative weights
+                value
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|w| w.is_finite() && *w >= 0.0)
+                    .ok_or_else(|| EdgeListError::InvalidWeight {
+                        line: line_number,
+                        value: value.to_string(),
+                    })?
+            }
+            None => 1.0,
+        };
+
+        edges.push((
+            source.0,
+            target.0,
+            layer_start,
+            layer_end,
+            layer_weight_index,
+            weight,
+        ));
+    }
+
+    Ok(merge_duplicate_edges(&edges, format.duplicates)?)
+}
+
+/// Same as [`read_edge_list`], but reads the file at `path`.
+///
+/// # Errors
+/// See [`read_edge_list`].
+#[allow(clippy::type_complexity)]
+pub fn read_edge_list_file(
+    path: impl AsRef<Path>,
+    format: &EdgeListFormat,
+    nodes: &mut NodeRegistry<String>,
+    layers: &mut LayerRegistry,
+) -> Result<
+    (
+        Vec<(usize, usize, usize, usize, usize, f32)>,
+        DuplicateSummary,
+    ),
+    EdgeListError,
+> {
+    let file = File::open(path)?;
+    read_edge_list(BufReader::new(file), format, nodes, layers)
+}
+
+/// Builds a graph from a single edge list file, along with the registries
+/// that map its node labels and layer names. Rows between the same nodes in
+/// different layers become parallel edges.
+///
+/// # Errors
+/// See [`read_edge_list`].
+pub fn load_edge_list(
+    path: impl AsRef<Path>,
+    format: &EdgeListFormat,
+) -> Result<
+    (
+        MultidistanceMultigraphHashmap,
+        NodeRegistry<String>,
+        LayerRegistry,
+    ),
+    EdgeListError,
+> {
+    let mut nodes = NodeRegistry::new();
+    let mut layers = LayerRegistry::new();
+    let (edges, _) = read_edge_list_file(path, format, &mut nodes, &mut layers)?;
+    Ok((
+        MultidistanceMultigraphHashmap::from_tuple_edge_list(&edges),
+        nodes,
+        layers,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MultiDistance, MultidistanceGraph, MultidistanceGraphHashmap, NodeID};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_read_hiring_edge_list() {
+        // one row per hire, the rank is used as the layer
+        let data = "# u\tv\trank\tgender\n\
+                    1\t2\tAsst\tM\n\
+                    1\t2\tAsst\tF\n\
+                    1\t2\tFull\tM\n\
+                    \n\
+                    2\t3\tAsst\tF\n";
+        let format = EdgeListFormat {
+            layers: LayerColumns::Single(2),
+            duplicates: DuplicatePolicy::Count,
+            ..EdgeListFormat::tsv()
+        };
+        let mut nodes = NodeRegistry::new();
+        let mut layers = LayerRegistry::new();
+        let (edges, summary) =
+            read_edge_list(data.as_bytes(), &format, &mut nodes, &mut layers).unwrap();
+
+        assert_eq!(
+            edges,
+            vec![
+                (0, 1, 0, 0, 0, 2.0),
+                (0, 1, 1, 1, 0, 1.0),
+                (1, 2, 0, 0, 0, 1.0)
+            ]
+        );
+        assert_eq!(summary.n_merged(), 1);
+        assert_eq!(summary.duplicates, BTreeMap::from([((0, 1, 0, 0, 0), 2)]));
+        assert_eq!(nodes.id("3"), Some(NodeID(2)));
+        assert_eq!(layers.layer_name(1), Some("Full"));
+    }
+
+    #[test]
+    fn test_read_layers_from_separate_files() {
+        let calls = "M25486 M25290 4\nM25290 M05001 1\n";
+        let mobility = "M25486\tM05001\t2.5\n";
+        let format = |layer: &str| EdgeListFormat {
+            layers: LayerColumns::Fixed(layer.to_string()),
+            weight_column: Some(2),
+            ..EdgeListFormat::default()
+        };
+
+        let mut nodes = NodeRegistry::new();
+        let mut layers = LayerRegistry::new();
+        let (mut edges, _) =
+            read_edge_list(calls.as_bytes(), &format("calls"), &mut nodes, &mut layers).unwrap();
+        edges.extend(
+            read_edge_list(
+                mobility.as_bytes(),
+                &format("mobility"),
+                &mut nodes,
+                &mut layers,
+            )
+            .unwrap()
+            .0,
+        );
+        let graph = MultidistanceGraphHashmap::from_tuple_edge_list(&edges);
+
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(layers.n_layers(), 2);
+        assert_eq!(
+            graph.edge_weight(NodeID(0), NodeID(2)),
+            Some(&MultiDistance::from_tuple(1, 1, 0, 2.5))
+        );
+    }
+
+    #[test]
+    fn test_load_edge_list_keeps_layers_apart() {
+        let path = std::env::temp_dir().join(format!("backbone-edges-{}.tsv", std::process::id()));
+        std::fs::write(&path, "a\tb\tcalls\t1\na\tb\tmobility\t2\n").unwrap();
+        let format = EdgeListFormat {
+            layers: LayerColumns::Single(2),
+            weight_column: Some(3),
+            ..EdgeListFormat::tsv()
+        };
+        let loaded = load_edge_list(&path, &format);
+        std::fs::remove_file(&path).unwrap();
+
+        let (graph, _, layers) = loaded.unwrap();
+        assert_eq!(layers.n_layers(), 2);
+        assert_eq!(
+            graph.parallel_edges(NodeID(0), NodeID(1)),
+            &[
+                MultiDistance::from_tuple(0, 0, 0, 1.0),
+                MultiDistance::from_tuple(1, 1, 0, 2.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_errors() {
+        let read = |data: &str, format: &EdgeListFormat| {
+            read_edge_list(
+                data.as_bytes(),
+                format,
+                &mut NodeRegistry::new(),
+                &mut LayerRegistry::new(),
+            )
+        };
+        let format = EdgeListFormat {
+            weight_column: Some(2),
+            duplicates: DuplicatePolicy::Error,
+            ..EdgeListFormat::csv()
+        };
+
+        assert!(read("u,v,w\na,b,1\n", &format).is_ok());
+        assert!(matches!(
+            read("u,v,w\na,b\n", &format),
+            Err(EdgeListError::MissingColumn { line: 2, column: 2 })
+        ));
+        for weight in ["x", "NaN", "inf", "-1"] {
+            assert!(matches!(
+                read(&format!("u,v,w\na,b,{weight}\n"), &format),
+                Err(EdgeListError::InvalidWeight { line: 2, .. })
+            ));
+        }
+        assert!(matches!(
+            read("u,v,w\na,b,1\na,b,2\n", &format),
+            Err(EdgeListError::Duplicate(_))
+        ));
+    }
+}
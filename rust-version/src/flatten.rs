@@ -0,0 +1,373 @@
+use crate::direct_backbone::MultilayerBackbone;
+use crate::multidistance::{MultiDistance, NodeID};
+use crate::multigraph::MultidistanceGraph;
+use crate::multiplex::StateNode;
+use crate::node_registry::NodeRegistry;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::str::FromStr;
+
+/// A weighted edge between (physical) nodes.
+pub type WeightedEdge = (NodeID, NodeID, f32);
+
+/// Which node pairs are kept when layers are collapsed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum EdgeSelection {
+    /// Pairs with an edge in at least one layer.
+    #[default]
+    Union,
+    /// Pairs with an edge in every layer.
+    Intersection,
+}
+
+/// How the weights of a node pair in the different layers are combined.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum WeightAggregation {
+    Sum,
+    #[default]
+    Min,
+    Max,
+    /// The number of layers with an edge between the pair.
+    Count,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseAggregationError(pub String);
+
+impl fmt::Display for ParseAggregationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown aggregation {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseAggregationError {}
+
+impl FromStr for EdgeSelection {
+    type Err = ParseAggregationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "union" => Ok(EdgeSelection::Union),
+            "intersection" => Ok(EdgeSelection::Intersection),
+            _ => Err(ParseAggregationError(s.to_string())),
+        }
+    }
+}
+
+impl FromStr for WeightAggregation {
+    type Err = ParseAggregationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sum" => Ok(WeightAggregation::Sum),
+            "min" => Ok(WeightAggregation::Min),
+            "max" => Ok(WeightAggregation::Max),
+            "count" => Ok(WeightAggregation::Count),
+            _ => Err(ParseAggregationError(s.to_string())),
+        }
+    }
+}
+
+/// Collapses `(source, target, layer, weight)` edges into one edge per node
+/// pair. A node pair is supported by a layer if it has an edge in that layer;
+/// with `EdgeSelection::Intersection` it must be supported by all `n_layers`
+/// layers. Several edges of a pair in the same layer are aggregated like
+/// edges in different layers, but count as one layer.
+///
+/// The result is sorted by node pair.
+pub fn flatten_edges(
+    edges: impl IntoIterator<Item = (NodeID, NodeID, usize, f32)>,
+    n_layers: usize,
+    selection: EdgeSelection,
+    aggregation: WeightAggregation,
+) -> Vec<WeightedEdge> {
+    let mut by_pair: BTreeMap<(NodeID, NodeID), BTreeMap<usize, Vec<f32>>> = BTreeMap::new();
+    for (source, target, layer, weight) in edges {
+        by_pair
+            .entry((source, target))
+            .or_default()
+            .entry(layer)
+            .or_default()
+            .push(weight);
+    }
+
+    by_pair
+        .into_iter()
+        .filter(|(_, layers)| selection == EdgeSelection::Union || layers.len() == n_layers)
+        .map(|((source, target), layers)| {
+            let weights = layers.values().flatten().copied();
+            #[allow(clippy::cast_precision_loss)] // layer counts are small
+            let weight = match aggregation {
+                WeightAggregation::Sum => weights.sum(),
+                WeightAggregation::Min => weights.fold(f32::INFINITY, f32::min),
+                WeightAggregation::Max => weights.fold(f32::NEG_INFINITY, f32::max),
+                WeightAggregation::Count => layers.len() as f32,
+            };
+            (source, target, weight)
+        })
+        .collect()
+}
+
+/// Projects the intralayer edges of a graph whose nodes are physical nodes,
+/// and whose edge weights carry the layers, e.g. one built by
+/// `MultidistanceGraphHashmap::from_tuple_edge_list`. An edge belongs to
+/// layer `l` if all its weights are in `l -> l`; interlayer edges are ignored,
+/// and so are zero-weight edges, which have no layer. The number of layers is
+/// the number of distinct layers of the edges.
+pub fn flatten_layered_graph(
+    graph: &impl MultidistanceGraph,
+    selection: EdgeSelection,
+    aggregation: WeightAggregation,
+) -> Vec<WeightedEdge> {
+    flatten_layered(graph, selection, aggregation, |_, _, _| true)
+}
+
+/// Same as [`flatten_layered_graph`], but only the edges of `graph` whose
+/// weight is one of the distances of `backbone` are used.
+pub fn flatten_layered_backbone(
+    graph: &impl MultidistanceGraph,
+    backbone: &MultilayerBackbone,
+    selection: EdgeSelection,
+    aggregation: WeightAggregation,
+) -> Vec<WeightedEdge> {
+    flatten_layered(graph, selection, aggregation, |source, target, weight| {
+        backbone
+            .get(source)
+            .and_then(|targets| targets.get(target))
+            .is_some_and(|distances| distances.contains(weight))
+    })
+}
+
+fn flatten_layered(
+    graph: &impl MultidistanceGraph,
+    selection: EdgeSelection,
+    aggregation: WeightAggregation,
+    keep: impl Fn(&NodeID, &NodeID, &MultiDistance) -> bool,
+) -> Vec<WeightedEdge> {
+    let mut layers = BTreeSet::new();
+    let mut intralayer = Vec::new();
+    for source in graph.nodes() {
+        for (target, weight) in graph.neighbor_edges(&source) {
+            layers.extend(
+                weight
+                    .total
+                    .keys()
+                    .flat_map(|l| [l.layer_start, l.layer_end]),
+            );
+            if !keep(&source, &target, &weight) {
+                continue;
+            }
+            let mut edge_layers = weight.total.keys().map(|l| (l.layer_start, l.layer_end));
+            if let Some((layer, layer_end)) = edge_layers.next() {
+                if layer == layer_end && edge_layers.all(|l| l == (layer, layer)) {
+                    intralayer.push((source, target, layer, weight.total.values().sum()));
+                }
+            }
+        }
+    }
+    flatten_edges(intralayer, layers.len(), selection, aggregation)
+}
+
+/// Projects the intralayer edges of a supra-graph onto its physical nodes.
+/// Coupling and other interlayer edges are ignored, and the number of layers
+/// is the number of distinct layers in `registry`.
+///
+/// # Panics
+/// Panics if the graph contains a node that is missing from `registry`.
+pub fn flatten_graph(
+    graph: &impl MultidistanceGraph,
+    registry: &NodeRegistry<StateNode>,
+    selection: EdgeSelection,
+    aggregation: WeightAggregation,
+) -> Vec<WeightedEdge> {
+    flatten_filtered(graph, registry, selection, aggregation, |_, _| true)
+}
+
+/// Same as [`flatten_graph`], but only the edges of `backbone` are used.
+///
+/// # Panics
+/// Panics if the graph contains a node that is missing from `registry`.
+pub fn flatten_backbone(
+    graph: &impl MultidistanceGraph,
+    backbone: &MultilayerBackbone,
+    registry: &NodeRegistry<StateNode>,
+    selection: EdgeSelection,
+    aggregation: WeightAggregation,
+) -> Vec<WeightedEdge> {
+    flatten_filtered(graph, registry, selection, aggregation, |source, target| {
+        backbone
+            .get(source)
+            .is_some_and(|targets| targets.contains_key(target))
+    })
+}
+
+fn flatten_filtered(
+    graph: &impl MultidistanceGraph,
+    registry: &NodeRegistry<StateNode>,
+    selection: EdgeSelection,
+    aggregation: WeightAggregation,
+    keep: impl Fn(&NodeID, &NodeID) -> bool,
+) -> Vec<WeightedEdge> {
+    let state_of = |id: &NodeID| {
+        *registry
+            .label(*id)
+            .unwrap_or_else(|| panic!("{id} has no state node"))
+    };
+    let n_layers = registry
+        .labels()
+        .map(|s| s.layer)
+        .collect::<BTreeSet<_>>()
+        .len();
+
+    let mut edges = Vec::new();
+    for source in graph.nodes() {
+        let from = state_of(&source);
+        for (target, distance) in graph.neighbor_edges(&source) {
+            let to = state_of(&target);
+            if from.layer == to.layer && keep(&source, &target) {
+                edges.push((
+                    from.node,
+                    to.node,
+                    from.layer,
+                    distance.total.values().sum(),
+                ));
+            }
+        }
+    }
+    flatten_edges(edges, n_layers, selection, aggregation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        coupled_tuple_edge_list, multilayer_backbone, CouplingPolicy, MultidistanceGraphHashmap,
+        MultidistanceMultigraphHashmap,
+    };
+
+    #[test]
+    fn test_flatten_multiplex() {
+        let layers = vec![
+            vec![(0, 1, 1.0), (1, 2, 1.0), (0, 2, 3.0)],
+            vec![(0, 1, 2.0), (0, 2, 1.0)],
+        ];
+        let (graph, registry) = MultidistanceGraphHashmap::from_layer_edge_lists(
+            &layers,
+            &CouplingPolicy::Uniform(0.0),
+        );
+
+        let flat =
+            |selection, aggregation| flatten_graph(&graph, &registry, selection, aggregation);
+        let (n0, n1, n2) = (NodeID(0), NodeID(1), NodeID(2));
+
+        assert_eq!(
+            flat(EdgeSelection::Union, WeightAggregation::Min),
+            vec![(n0, n1, 1.0), (n0, n2, 1.0), (n1, n2, 1.0)]
+        );
+        assert_eq!(
+            flat(EdgeSelection::Intersection, WeightAggregation::Max),
+            vec![(n0, n1, 2.0), (n0, n2, 3.0)]
+        );
+        assert_eq!(
+            flat(EdgeSelection::Union, WeightAggregation::Sum),
+            vec![(n0, n1, 3.0), (n0, n2, 4.0), (n1, n2, 1.0)]
+        );
+        assert_eq!(
+            flat(EdgeSelection::Union, WeightAggregation::Count),
+            vec![(n0, n1, 2.0), (n0, n2, 2.0), (n1, n2, 1.0)]
+        );
+
+        // 0 -> 2 in layer 0 is beaten by 0 -> 1 -> 2
+        let (edges, _) = coupled_tuple_edge_list(&layers, &CouplingPolicy::Uniform(0.0));
+        let backbone = multilayer_backbone(&edges);
+        assert_eq!(
+            flatten_backbone(
+                &graph,
+                &backbone,
+                &registry,
+                EdgeSelection::Intersection,
+                WeightAggregation::Count
+            ),
+            vec![(n0, n1, 2.0)]
+        );
+    }
+
+    #[test]
+    fn test_flatten_layered_graph() {
+        let edges = [
+            (0, 1, 0, 0, 0, 1.0),
+            (0, 1, 1, 1, 0, 2.0),
+            (0, 1, 1, 1, 0, 4.0),
+            (1, 2, 0, 1, 0, 1.0),
+            (0, 2, 1, 1, 0, 3.0),
+        ];
+        let (n0, n1, n2) = (NodeID(0), NodeID(1), NodeID(2));
+
+        let multigraph = MultidistanceMultigraphHashmap::from_tuple_edge_list(&edges);
+        let flat =
+            |selection, aggregation| flatten_layered_graph(&multigraph, selection, aggregation);
+        assert_eq!(
+            flat(EdgeSelection::Union, WeightAggregation::Sum),
+            vec![(n0, n1, 7.0), (n0, n2, 3.0)]
+        );
+        assert_eq!(
+            flat(EdgeSelection::Union, WeightAggregation::Max),
+            vec![(n0, n1, 4.0), (n0, n2, 3.0)]
+        );
+        assert_eq!(
+            flat(EdgeSelection::Intersection, WeightAggregation::Count),
+            vec![(n0, n1, 2.0)]
+        );
+
+        // 0 -> 2 is beaten by 0 -> 1 -> 2 in layer 0
+        let edges = [
+            (0, 1, 0, 0, 0, 1.0),
+            (1, 2, 0, 0, 0, 1.0),
+            (0, 2, 0, 0, 0, 3.0),
+        ];
+        let graph = MultidistanceGraphHashmap::from_tuple_edge_list(&edges);
+        let backbone = multilayer_backbone(&edges);
+        assert_eq!(
+            flatten_layered_backbone(
+                &graph,
+                &backbone,
+                EdgeSelection::Union,
+                WeightAggregation::Min
+            ),
+            vec![(n0, n1, 1.0), (n1, n2, 1.0)]
+        );
+    }
+
+    #[test]
+    fn test_flatten_layered_backbone_uses_edge_weights() {
+        // the path 0 -> 2 -> 1 in layer 1 is a backbone distance of (0, 1),
+        // but there is no edge from 0 to 1 in layer 1
+        let edges = [
+            (0, 1, 0, 0, 0, 5.0),
+            (0, 2, 1, 1, 0, 1.0),
+            (2, 1, 1, 1, 0, 1.0),
+        ];
+        let graph = MultidistanceGraphHashmap::from_tuple_edge_list(&edges);
+        let backbone = multilayer_backbone(&edges);
+        let flat = |selection, aggregation| {
+            flatten_layered_backbone(&graph, &backbone, selection, aggregation)
+        };
+        let (n0, n1, n2) = (NodeID(0), NodeID(1), NodeID(2));
+
+        assert_eq!(
+            flat(EdgeSelection::Intersection, WeightAggregation::Min),
+            vec![]
+        );
+        assert_eq!(
+            flat(EdgeSelection::Union, WeightAggregation::Min),
+            vec![(n0, n1, 5.0), (n0, n2, 1.0), (n2, n1, 1.0)]
+        );
+    }
+
+    #[test]
+    fn test_parse_aggregation() {
+        assert_eq!("intersection".parse(), Ok(EdgeSelection::Intersection));
+        assert_eq!("count".parse(), Ok(WeightAggregation::Count));
+        assert!("median".parse::<WeightAggregation>().is_err());
+    }
+}
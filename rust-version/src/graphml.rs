@@ -0,0 +1,678 @@
+use crate::closure::MultidistanceClosure;
+use crate::layer_registry::{LayerError, LayerRegistry};
+use crate::multidistance::{EdgeLayerID, MultiDistance, NodeID};
+use crate::multigraph::MultidistanceMultigraphHashmap;
+use crate::node_registry::NodeRegistry;
+use quick_xml::escape::escape;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+
+/// Where the layers of a GraphML edge come from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GraphmlLayers {
+    /// Every edge lies in the named layer.
+    Fixed(String),
+    /// The layer name is an edge attribute; edges are intralayer.
+    EdgeAttribute(String),
+    /// The source and target layer names are these edge attributes.
+    EdgeAttributes(String, String),
+    /// Every node has its layer as this attribute, e.g. the state nodes of a
+    /// supra-graph. An edge goes from the layer of its source to the layer of
+    /// its target.
+    NodeAttribute(String),
+}
+
+/// Which GraphML attributes hold the edge weights and layers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GraphmlFormat {
+    /// Edge attribute with the edge weight.
+    pub weight_attribute: String,
+    pub layers: GraphmlLayers,
+    /// Name of the layer weight that the weights are stored under.
+    pub layer_weight: String,
+    /// Edge attribute with the name of the layer weight of each edge, as
+    /// written by [`write_backbone_graphml`]. Edges without it, or all edges if
+    /// this is `None`, use `layer_weight`.
+    pub layer_weight_attribute: Option<String>,
+}
+
+impl Default for GraphmlFormat {
+    /// The format written by `create_net.py`.
+    fn default() -> Self {
+        GraphmlFormat {
+            weight_attribute: "distance".to_string(),
+            layers: GraphmlLayers::Fixed("0".to_string()),
+            layer_weight: "weight".to_string(),
+            layer_weight_attribute: Some("layer_weight".to_string()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum GraphmlError {
+    Io(io::Error),
+    Xml(quick_xml::Error),
+    /// An element lacks a required XML or GraphML attribute.
+    MissingAttribute {
+        element: String,
+        attribute: String,
+    },
+    InvalidWeight {
+        element: String,
+        value: String,
+    },
+    UnknownNode(NodeID),
+    Layer(LayerError),
+}
+
+impl fmt::Display for GraphmlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphmlError::Io(e) => write!(f, "{e}"),
+            GraphmlError::Xml(e) => write!(f, "{e}"),
+            GraphmlError::MissingAttribute { element, attribute } => {
+                write!(f, "{element} has no attribute {attribute:?}")
+            }
+            GraphmlError::InvalidWeight { element, value } => {
+                write!(f, "{element} has invalid weight {value:?}")
+            }
+            GraphmlError::UnknownNode(node) => write!(f, "{node} has no label"),
+            GraphmlError::Layer(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for GraphmlError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GraphmlError::Io(e) => Some(e),
+            GraphmlError::Xml(e) => Some(e),
+            GraphmlError::Layer(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for GraphmlError {
+    fn from(e: io::Error) -> Self {
+        GraphmlError::Io(e)
+    }
+}
+
+impl From<quick_xml::Error> for GraphmlError {
+    fn from(e: quick_xml::Error) -> Self {
+        GraphmlError::Xml(e)
+    }
+}
+
+impl From<quick_xml::events::attributes::AttrError> for GraphmlError {
+    fn from(e: quick_xml::events::attributes::AttrError) -> Self {
+        GraphmlError::Xml(e.into())
+    }
+}
+
+impl From<LayerError> for GraphmlError {
+    fn from(e: LayerError) -> Self {
+        GraphmlError::Layer(e)
+    }
+}
+
+/// A node or edge as it appears in the file, with its `<data>` values keyed
+/// by attribute name. Only the keys declared for its domain (or for `all`)
+/// are used.
+#[derive(Default)]
+struct Element {
+    id: String,
+    source: String,
+    target: String,
+    directed: Option<bool>,
+    data: HashMap<String, String>,
+}
+
+#[derive(Default)]
+struct Document {
+    /// `<key>` id to (domain, attribute name).
+    keys: HashMap<String, (String, String)>,
+    /// (domain, attribute name) to the value of `<default>`.
+    defaults: HashMap<(String, String), String>,
+    directed: bool,
+    nodes: Vec<Element>,
+    edges: Vec<Element>,
+}
+
+impl Document {
+    fn attribute<'a>(
+        &'a self,
+        element: &'a Element,
+        domain: &str,
+        name: &str,
+    ) -> Option<&'a String> {
+        element.data.get(name).or_else(|| {
+            [domain, "all"]
+                .into_iter()
+                .find_map(|d| self.defaults.get(&(d.to_string(), name.to_string())))
+        })
+    }
+}
+
+fn xml_attribute(start: &BytesStart, name: &str) -> Result<Option<String>, GraphmlError> {
+    for attribute in start.attributes() {
+        let attribute = attribute?;
+        if attribute.key.as_ref() == name.as_bytes() {
+            return Ok(Some(attribute.unescape_value()?.into_owned()));
+        }
+    }
+    Ok(None)
+}
+
+fn required_xml_attribute(start: &BytesStart, name: &str) -> Result<String, GraphmlError> {
+    xml_attribute(start, name)?.ok_or_else(|| GraphmlError::MissingAttribute {
+        element: String::from_utf8_lossy(start.name().as_ref()).into_owned(),
+        attribute: name.to_string(),
+    })
+}
+
+fn parse_document(reader: impl BufRead) -> Result<Document, GraphmlError> {
+    let mut reader = Reader::from_reader(reader);
+    reader.config_mut().trim_text(true);
+
+    let mut document = Document {
+        directed: true,
+        ..Document::default()
+    };
+    let mut buf = Vec::new();
+    let mut current: Option<(bool, Element)> = None; // (is_edge, element)
+    let mut current_key: Option<(String, String)> = None;
+    let mut text: Option<(String, String)> = None; // (attribute name, text)
+
+    loop {
+        let event = reader.read_event_into(&mut buf)?;
+        let is_empty = matches!(event, Event::Empty(_));
+        match event {
+            Event::Start(start) | Event::Empty(start) => match start.local_name().as_ref() {
+                b"key" => {
+                    let id = required_xml_attribute(&start, "id")?;
+                    let domain = xml_attribute(&start, "for")?.unwrap_or_else(|| "all".into());
+                    let name = xml_attribute(&start, "attr.name")?.unwrap_or_else(|| id.clone());
+                    document.keys.insert(id, (domain.clone(), name.clone()));
+                    if !is_empty {
+                        current_key = Some((domain, name));
+                    }
+                }
+                b"default" => {
+                    if let Some((_, name)) = &current_key {
+                        text = Some((name.clone(), String::new()));
+                    }
+                }
+                b"graph" => {
+                    document.directed =
+                        xml_attribute(&start, "edgedefault")?.as_deref() != Some("undirected");
+                }
+                b"node" => {
+                    let node = Element {
+                        id: required_xml_attribute(&start, "id")?,
+                        ..Element::default()
+                    };
+                    if is_empty {
+                        document.nodes.push(node);
+                    } else {
+                        current = Some((false, node));
+                    }
+                }
+                b"edge" => {
+                    let edge = Element {
+                        source: required_xml_attribute(&start, "source")?,
+                        target: required_xml_attribute(&start, "target")?,
+                        directed: xml_attribute(&start, "directed")?.map(|d| d == "true"),
+                        ..Element::default()
+                    };
+                    if is_empty {
+                        document.edges.push(edge);
+                    } else {
+                        current = Some((true, edge));
+                    }
+                }
+                b"data" => {
+                    let key = required_xml_attribute(&start, "key")?;
+                    let name = match (&current, document.keys.get(&key)) {
+                        (Some(_), None) => Some(key),
+                        (Some((is_edge, _)), Some((domain, name)))
+                            if domain == "all"
+                                || domain == if *is_edge { "edge" } else { "node" } =>
+                        {
+                            Some(name.clone())
+                        }
+                        _ => None,
+                    };
+                    if let Some(name) = name {
+                        if is_empty {
+                            if let Some((_, element)) = &mut current {
+                                element.data.insert(name, String::new());
+                            }
+                        } else {
+                            text = Some((name, String::new()));
+                        }
+                    }
+                }
+                _ => {}
+            },
+            Event::Text(content) => {
+                if let Some((_, value)) = &mut text {
+                    value.push_str(&content.unescape()?);
+                }
+            }
+            Event::CData(content) => {
+                if let Some((_, value)) = &mut text {
+                    value.push_str(&String::from_utf8_lossy(&content));
+                }
+            }
+            Event::End(end) => match end.local_name().as_ref() {
+                b"key" => current_key = None,
+                b"default" => {
+                    if let (Some((domain, _)), Some((name, value))) = (&current_key, text.take()) {
+                        document.defaults.insert((domain.clone(), name), value);
+                    }
+                }
+                b"data" => {
+                    if let (Some((_, element)), Some((name, value))) = (&mut current, text.take()) {
+                        element.data.insert(name, value);
+                    }
+                }
+                b"node" | b"edge" => match current.take() {
+                    Some((true, edge)) => document.edges.push(edge),
+                    Some((false, node)) => document.nodes.push(node),
+                    None => {}
+                },
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(document)
+}
+
+/// Parses a GraphML document into the tuple format used by the rest of the
+/// crate. Node ids, layer names and layer weight names are interned in `nodes`
+/// and `layers`; every declared node is registered, even without edges.
+/// Undirected edges are listed in both directions.
+///
+/// # Errors
+/// Returns a `GraphmlError` if the document is malformed or an edge lacks its
+/// weight or layer attributes.
+#[allow(clippy::type_complexity)]
+pub fn read_graphml(
+    reader: impl BufRead,
+    format: &GraphmlFormat,
+    nodes: &mut NodeRegistry<String>,
+    layers: &mut LayerRegistry,
+) -> Result<Vec<(usize, usize, usize, usize, usize, f32)>, GraphmlError> {
+    let document = parse_document(reader)?;
+    let default_layer_weight = layers.add_layer_weight(&format.layer_weight);
+    let node_elements: HashMap<&str, &Element> =
+        document.nodes.iter().map(|n| (n.id.as_str(), n)).collect();
+    for node in &document.nodes {
+        nodes.get_or_insert(node.id.clone());
+    }
+
+    let mut edges = Vec::new();
+    for edge in &document.edges {
+        let description = || format!("edge {} -> {}", edge.source, edge.target);
+        let edge_attribute = |name: &str| {
+            document
+                .attribute(edge, "edge", name)
+                .ok_or_else(|| GraphmlError::MissingAttribute {
+                    element: description(),
+                    attribute: name.to_string(),
+                })
+        };
+        let node_attribute = |id: &str, name: &str| {
+            node_elements
+                .get(id)
+                .and_then(|node| document.attribute(node, "node", name))
+                .ok_or_else(|| GraphmlError::MissingAttribute {
+                    element: format!("node {id}"),
+                    attribute: name.to_string(),
+                })
+        };
+
+        let value = edge_attribute(&format.weight_attribute)?;
+        // every search assumes non-negative weights
+        let weight = value
+            .trim()
+            .parse::<f32>()
+            .ok()
+            .filter(|w| w.is_finite() && *w >= 0.0)
+            .ok_or_else(|| GraphmlError::InvalidWeight {
+                element: description(),
+                value: value.clone(),
+            })?;
+        let (layer_start, layer_end) = match &format.layers {
+            GraphmlLayers::Fixed(name) => {
+                let layer = layers.add_layer(name);
+                (layer, layer)
+            }
+            GraphmlLayers::EdgeAttribute(name) => {
+                let layer = layers.add_layer(edge_attribute(name)?);
+                (layer, layer)
+            }
+            GraphmlLayers::EdgeAttributes(start, end) => (
+                layers.add_layer(edge_attribute(start)?),
+                layers.add_layer(edge_attribute(end)?),
+            ),
+            GraphmlLayers::NodeAttribute(name) => (
+                layers.add_layer(node_attribute(&edge.source, name)?),
+                layers.add_layer(node_attribute(&edge.target, name)?),
+            ),
+        };
+
+        let layer_weight_index = match format
+            .layer_weight_attribute
+            .as_deref()
+            .and_then(|name| document.attribute(edge, "edge", name))
+        {
+            Some(name) => layers.add_layer_weight(name),
+            None => default_layer_weight,
+        };
+
+        let NodeID(source) = nodes.get_or_insert(edge.source.clone());
+        let NodeID(target) = nodes.get_or_insert(edge.target.clone());
+        edges.push((
+            source,
+            target,
+            layer_start,
+            layer_end,
+            layer_weight_index,
+            weight,
+        ));
+        if !edge.directed.unwrap_or(document.directed) {
+            edges.push((
+                target,
+                source,
+                layer_end,
+                layer_start,
+                layer_weight_index,
+                weight,
+            ));
+        }
+    }
+    Ok(edges)
+}
+
+/// Builds a graph from a GraphML file, along with the registries that map
+/// its node ids and layer names. Parallel edges are kept.
+///
+/// # Errors
+/// See [`read_graphml`].
+pub fn load_graphml(
+    path: impl AsRef<Path>,
+    format: &GraphmlFormat,
+) -> Result<
+    (
+        MultidistanceMultigraphHashmap,
+        NodeRegistry<String>,
+        LayerRegistry,
+    ),
+    GraphmlError,
+> {
+    let mut nodes = NodeRegistry::new();
+    let mut layers = LayerRegistry::new();
+    let file = File::open(path)?;
+    let edges = read_graphml(BufReader::new(file), format, &mut nodes, &mut layers)?;
+    Ok((
+        MultidistanceMultigraphHashmap::from_tuple_edge_list(&edges),
+        nodes,
+        layers,
+    ))
+}
+
+const BACKBONE_KEYS: [(&str, &str); 6] = [
+    ("layer_start", "string"),
+    ("layer_end", "string"),
+    ("layer_weight", "string"),
+    ("distance", "double"),
+    ("metric", "boolean"),
+    ("pareto_distances", "string"),
+];
+
+/// Writes every node of `nodes` and every edge of the tuple edge list `edges`
+/// together with its classification. Each edge has the attributes
+///
+/// * `layer_start`, `layer_end`, `layer_weight`: the names of its layers, also
+///   for zero-weight edges,
+/// * `distance`: the edge weight,
+/// * `metric`: whether the edge belongs to the backbone,
+/// * `pareto_distances`: the Pareto shortest distances between its endpoints,
+///   as a JSON list of `{"layer_start->layer_end:layer_weight": value}`
+///   objects.
+///
+/// The output can be read back with `GraphmlLayers::EdgeAttributes`.
+///
+/// # Errors
+/// Returns a `GraphmlError` if writing fails, or if a node or layer of an
+/// edge is missing from `nodes` or `layers`.
+pub fn write_backbone_graphml(
+    mut writer: impl Write,
+    edges: &[(usize, usize, usize, usize, usize, f32)],
+    closure: &MultidistanceClosure,
+    nodes: &NodeRegistry<String>,
+    layers: &LayerRegistry,
+) -> Result<(), GraphmlError> {
+    let label = |id: NodeID| {
+        nodes
+            .label(id)
+            .map(|l| escape(l.as_str()))
+            .ok_or(GraphmlError::UnknownNode(id))
+    };
+
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        writer,
+        r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+    )?;
+    for (i, (name, kind)) in BACKBONE_KEYS.iter().enumerate() {
+        writeln!(
+            writer,
+            r#"  <key id="d{i}" for="edge" attr.name="{name}" attr.type="{kind}"/>"#
+        )?;
+    }
+    writeln!(writer, r#"  <graph edgedefault="directed">"#)?;
+
+    for node in nodes.labels() {
+        writeln!(writer, r#"    <node id="{}"/>"#, escape(node.as_str()))?;
+    }
+
+    let mut edges = edges.to_vec();
+    edges.sort_by_key(|e| (e.0, e.1));
+    for (source, target, layer_start, layer_end, layer_weight_index, weight) in edges {
+        let (source, target) = (NodeID(source), NodeID(target));
+        let pareto = closure
+            .get(&source)
+            .and_then(|targets| targets.get(&target))
+            .map_or(&[][..], Vec::as_slice);
+        let ((start, end), layer_weight) = layers.edge_layer_names(&EdgeLayerID {
+            layer_start,
+            layer_end,
+            layer_weight_index,
+        })?;
+        let distance =
+            MultiDistance::from_tuple(layer_start, layer_end, layer_weight_index, weight);
+        let data = [
+            start,
+            end,
+            layer_weight,
+            weight.to_string(),
+            pareto.contains(&distance).to_string(),
+            pareto_json(pareto, layers),
+        ];
+
+        writeln!(
+            writer,
+            r#"    <edge source="{}" target="{}">"#,
+            label(source)?,
+            label(target)?
+        )?;
+        for (key, value) in data.iter().enumerate() {
+            writeln!(
+                writer,
+                r#"      <data key="d{key}">{}</data>"#,
+                escape(value.as_str())
+            )?;
+        }
+        writeln!(writer, "    </edge>")?;
+    }
+
+    writeln!(writer, "  </graph>")?;
+    writeln!(writer, "</graphml>")?;
+    Ok(())
+}
+
+fn pareto_json(distances: &[MultiDistance], layers: &LayerRegistry) -> String {
+    let mut objects = Vec::new();
+    for distance in distances {
+        let mut entries: Vec<String> = distance
+            .total
+            .iter()
+            .map(|(layer, value)| {
+                format!("{}: {value}", json_string(&layers.edge_layer_label(layer)))
+            })
+            .collect();
+        entries.sort_unstable();
+        objects.push(format!("{{{}}}", entries.join(", ")));
+    }
+    format!("[{}]", objects.join(", "))
+}
+
+fn json_string(s: &str) -> String {
+    let mut quoted = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{multidistance_closure, MultidistanceGraphHashmap};
+
+    #[test]
+    fn test_read_graphml() {
+        // trimmed from data/academic_hiring/raw_data/history/network.graphml,
+        // plus an undirected edge and a layer per node
+        let data = r#"<?xml version='1.0' encoding='utf-8'?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+  <key id="d5" for="edge" attr.name="distance" attr.type="double" />
+  <key id="d6" for="node" attr.name="distance" attr.type="double" />
+  <key id="d2" for="node" attr.name="Institution" attr.type="string">
+    <default>calls</default>
+  </key>
+  <graph edgedefault="directed">
+    <node id="1">
+      <data key="d2">mobility</data>
+    </node>
+    <node id="2" />
+    <node id="3" />
+    <edge source="1" target="2">
+      <data key="d5">0.5</data>
+      <data key="d6">7</data>
+    </edge>
+    <edge source="2" target="3" directed="false">
+      <data key="d5">1.25</data>
+    </edge>
+  </graph>
+</graphml>"#;
+
+        let read = |format: &GraphmlFormat| {
+            let mut nodes = NodeRegistry::new();
+            let mut layers = LayerRegistry::new();
+            read_graphml(data.as_bytes(), format, &mut nodes, &mut layers)
+                .map(|edges| (edges, nodes, layers))
+        };
+
+        let (edges, nodes, _) = read(&GraphmlFormat::default()).unwrap();
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(
+            edges,
+            vec![
+                (0, 1, 0, 0, 0, 0.5),
+                (1, 2, 0, 0, 0, 1.25),
+                (2, 1, 0, 0, 0, 1.25)
+            ]
+        );
+
+        let (edges, _, layers) = read(&GraphmlFormat {
+            layers: GraphmlLayers::NodeAttribute("Institution".to_string()),
+            ..GraphmlFormat::default()
+        })
+        .unwrap();
+        assert_eq!(edges[0], (0, 1, 0, 1, 0, 0.5));
+        assert_eq!(layers.layer_name(1), Some("calls"));
+
+        assert!(matches!(
+            read(&GraphmlFormat {
+                weight_attribute: "weight".to_string(),
+                ..GraphmlFormat::default()
+            }),
+            Err(GraphmlError::MissingAttribute { .. })
+        ));
+    }
+
+    #[test]
+    fn test_backbone_round_trip() {
+        let mut nodes = NodeRegistry::new();
+        let mut layers = LayerRegistry::from_layer_names(&["calls", "mobility"]);
+        let cost = layers.add_layer_weight("cost");
+        let mut edges = nodes.intern_edge_list(&[
+            ("a&b".to_string(), "c".to_string(), 0, 0, 0, 1.0),
+            ("c".to_string(), "a&b".to_string(), 1, 1, cost, 2.0),
+            ("c".to_string(), "d".to_string(), 0, 0, 0, 1.0),
+            ("a&b".to_string(), "d".to_string(), 0, 0, 0, 3.0),
+            ("d".to_string(), "e".to_string(), 0, 1, 0, 1.0),
+            // a zero-cost coupling edge, whose weight has no layer
+            ("e".to_string(), "d".to_string(), 1, 0, 0, 0.0),
+        ]);
+        nodes.get_or_insert("isolated".to_string());
+        let graph = MultidistanceGraphHashmap::from_tuple_edge_list(&edges);
+        let closure = multidistance_closure(&graph);
+
+        let mut written = Vec::new();
+        write_backbone_graphml(&mut written, &edges, &closure, &nodes, &layers).unwrap();
+        let written = String::from_utf8(written).unwrap();
+        assert!(written.contains(r#"<edge source="a&amp;b" target="d">"#));
+        assert!(written.contains(r#"<data key="d4">false</data>"#));
+        assert!(written.contains(r#"<data key="d1">mobility</data>"#));
+        assert!(written.contains(r#"[{&quot;calls-&gt;calls:weight&quot;: 2}]"#));
+
+        let mut read_nodes = NodeRegistry::new();
+        let read_edges = read_graphml(
+            written.as_bytes(),
+            &GraphmlFormat {
+                layers: GraphmlLayers::EdgeAttributes(
+                    "layer_start".to_string(),
+                    "layer_end".to_string(),
+                ),
+                ..GraphmlFormat::default()
+            },
+            &mut read_nodes,
+            &mut layers,
+        )
+        .unwrap();
+        edges.sort_by_key(|e| (e.0, e.1));
+        assert_eq!(read_edges, edges);
+        assert_eq!(read_nodes.id("a&b"), Some(NodeID(0)));
+        assert_eq!(read_nodes.len(), nodes.len());
+    }
+}
@@ -0,0 +1,164 @@
+use crate::direct_backbone::fast_backbone_costa_parallel;
+use crate::multidistance::NodeID;
+use crate::multigraph::{MultidistanceGraph, MultidistanceGraphHashmap};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Splits the intralayer edges of `graph` into one single-layer graph per
+/// layer. An edge belongs to layer `l` if its weight lies entirely in
+/// `layer_start == layer_end == l`; interlayer edges and zero-weight edges
+/// (whose layer is unknown) are left out.
+#[must_use]
+pub fn layer_subgraphs(
+    graph: &impl MultidistanceGraph,
+) -> BTreeMap<usize, MultidistanceGraphHashmap> {
+    let mut layers: BTreeMap<usize, MultidistanceGraphHashmap> = BTreeMap::new();
+    for source in graph.nodes() {
+        for (target, distance) in graph.neighbor_edges(&source) {
+            let mut edge_layers = distance.total.keys();
+            if let (Some(layer), None) = (edge_layers.next(), edge_layers.next()) {
+                if layer.layer_start == layer.layer_end {
+                    layers.entry(layer.layer_start).or_default().add_edge(
+                        source,
+                        target,
+                        distance.clone(),
+                    );
+                }
+            }
+        }
+    }
+    layers
+}
+
+/// Computes the classic metric backbone of every layer independently.
+#[must_use]
+pub fn layer_backbones(
+    graph: &impl MultidistanceGraph,
+) -> BTreeMap<usize, BTreeSet<(NodeID, NodeID)>> {
+    layer_subgraphs(graph)
+        .into_iter()
+        .map(|(layer, mut subgraph)| {
+            fast_backbone_costa_parallel(&mut subgraph);
+            (layer, edge_set(&subgraph))
+        })
+        .collect()
+}
+
+/// Intralayer edges of a graph, split by which backbone keeps them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BackboneComparison {
+    /// Edges kept by the multilayer backbone but by no layer backbone.
+    pub multilayer_only: BTreeSet<(NodeID, NodeID)>,
+    /// Edges kept by a layer backbone but not by the multilayer backbone.
+    pub layer_only: BTreeSet<(NodeID, NodeID)>,
+    /// Edges kept by both.
+    pub both: BTreeSet<(NodeID, NodeID)>,
+}
+
+/// Compares the multilayer backbone of `graph` with the backbones of its
+/// layers. Only intralayer edges (see [`layer_subgraphs`]) are compared,
+/// because no layer backbone can contain an interlayer edge.
+#[must_use]
+pub fn compare_backbones(graph: &MultidistanceGraphHashmap) -> BackboneComparison {
+    let layers = layer_backbones(graph);
+    let in_layer_backbone: BTreeSet<(NodeID, NodeID)> =
+        layers.values().flatten().copied().collect();
+    let intralayer_edges: BTreeSet<(NodeID, NodeID)> =
+        layer_subgraphs(graph).values().flat_map(edge_set).collect();
+
+    let mut multilayer = graph.clone();
+    fast_backbone_costa_parallel(&mut multilayer);
+    let in_multilayer_backbone: BTreeSet<(NodeID, NodeID)> = edge_set(&multilayer)
+        .intersection(&intralayer_edges)
+        .copied()
+        .collect();
+
+    BackboneComparison {
+        multilayer_only: in_multilayer_backbone
+            .difference(&in_layer_backbone)
+            .copied()
+            .collect(),
+        layer_only: in_layer_backbone
+            .difference(&in_multilayer_backbone)
+            .copied()
+            .collect(),
+        both: in_multilayer_backbone
+            .intersection(&in_layer_backbone)
+            .copied()
+            .collect(),
+    }
+}
+
+fn edge_set(graph: &impl MultidistanceGraph) -> BTreeSet<(NodeID, NodeID)> {
+    graph
+        .nodes()
+        .into_iter()
+        .flat_map(|source| {
+            graph
+                .neighbor_edges(&source)
+                .into_iter()
+                .map(move |(target, _)| (source, target))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MultiDistance;
+
+    #[test]
+    fn test_compare_with_layer_backbones() {
+        // nodes 0-2 live in layer 0, nodes 3-5 are their copies in layer 1
+        let graph = MultidistanceGraphHashmap::from_tuple_edge_list(&[
+            (0, 1, 0, 0, 0, 1.0),
+            (1, 2, 0, 0, 0, 1.0),
+            (0, 2, 0, 0, 0, 3.0),
+            (3, 4, 1, 1, 0, 1.0),
+            (3, 5, 1, 1, 0, 4.0),
+            (4, 5, 1, 1, 0, 5.0),
+            (0, 3, 0, 1, 0, 1.0),
+            (5, 2, 1, 0, 0, 1.0),
+        ]);
+
+        let layers = layer_backbones(&graph);
+        assert_eq!(
+            layers[&0],
+            BTreeSet::from([(NodeID(0), NodeID(1)), (NodeID(1), NodeID(2))])
+        );
+        assert_eq!(
+            layers[&1],
+            BTreeSet::from([
+                (NodeID(3), NodeID(4)),
+                (NodeID(3), NodeID(5)),
+                (NodeID(4), NodeID(5))
+            ])
+        );
+
+        // an intralayer edge can only be dominated by a path that adds nothing
+        // to the other layers, so without zero weights the backbones agree
+        let comparison = compare_backbones(&graph);
+        assert!(comparison.multilayer_only.is_empty());
+        assert!(comparison.layer_only.is_empty());
+        assert_eq!(comparison.both.len(), 5);
+
+        // a zero-weight detour through layer 0 dominates 3 -> 5, which only
+        // the multilayer backbone can see
+        let mut coupled = graph.clone();
+        for (from, to, layer_start, layer_end) in
+            [(3, 0, 1, 0), (0, 6, 0, 0), (6, 2, 0, 0), (2, 5, 0, 1)]
+        {
+            coupled.add_edge(
+                NodeID(from),
+                NodeID(to),
+                MultiDistance::from_tuple(layer_start, layer_end, 0, 0.0),
+            );
+        }
+        let comparison = compare_backbones(&coupled);
+        assert!(comparison.multilayer_only.is_empty());
+        assert_eq!(
+            comparison.layer_only,
+            BTreeSet::from([(NodeID(3), NodeID(5))])
+        );
+        assert_eq!(comparison.both.len(), 4);
+    }
+}
@@ -0,0 +1,355 @@
+use crate::multidistance::{EdgeLayerID, MultiDistance, NodeID};
+use crate::multigraph::MultidistanceGraph;
+use std::collections::HashMap;
+use std::fmt;
+
+/// A tuple edge whose layers and layer weight are given by name. The fields
+/// are source node, target node, source layer, target layer, weight name and
+/// edge weight.
+pub type NamedLayerTupleEdge = (usize, usize, String, String, String, f32);
+
+/// Layer names of an `EdgeLayerID`, in the same
+/// `((layer_start, layer_end), layer_weight)` shape that is used when layers
+/// are handed to Python.
+pub type NamedEdgeLayer = ((String, String), String);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LayerError {
+    UnknownLayer(usize),
+    UnknownLayerWeight(usize),
+    UnknownLayerName(String),
+    UnknownLayerWeightName(String),
+}
+
+impl fmt::Display for LayerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayerError::UnknownLayer(index) => write!(f, "no layer with index {index}"),
+            LayerError::UnknownLayerWeight(index) => {
+                write!(f, "no layer weight with index {index}")
+            }
+            LayerError::UnknownLayerName(name) => write!(f, "no layer named {name:?}"),
+            LayerError::UnknownLayerWeightName(name) => {
+                write!(f, "no layer weight named {name:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for LayerError {}
+
+/// Maps layer names (e.g. `calls`/`mobility`) and layer weight names to the
+/// indices stored in an `EdgeLayerID`.
+///
+/// Layers and layer weights are numbered separately, consecutively from zero
+/// in order of registration.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LayerRegistry {
+    layers: Vec<String>,
+    layer_indices: HashMap<String, usize>,
+    weights: Vec<String>,
+    weight_indices: HashMap<String, usize>,
+}
+
+impl LayerRegistry {
+    #[must_use]
+    pub fn new() -> LayerRegistry {
+        LayerRegistry::default()
+    }
+
+    /// Creates a registry with the given layers and a single layer weight
+    /// called `weight`.
+    #[must_use]
+    pub fn from_layer_names<S: AsRef<str>>(names: &[S]) -> LayerRegistry {
+        let mut registry = LayerRegistry::new();
+        for name in names {
+            registry.add_layer(name.as_ref());
+        }
+        registry.add_layer_weight("weight");
+        registry
+    }
+
+    /// Returns the index of layer `name`, registering it first if needed.
+    pub fn add_layer(&mut self, name: &str) -> usize {
+        get_or_insert(&mut self.layers, &mut self.layer_indices, name)
+    }
+
+    /// Returns the index of layer weight `name`, registering it first if
+    /// needed.
+    pub fn add_layer_weight(&mut self, name: &str) -> usize {
+        get_or_insert(&mut self.weights, &mut self.weight_indices, name)
+    }
+
+    #[must_use]
+    pub fn layer_index(&self, name: &str) -> Option<usize> {
+        self.layer_indices.get(name).copied()
+    }
+
+    #[must_use]
+    pub fn layer_name(&self, index: usize) -> Option<&str> {
+        self.layers.get(index).map(String::as_str)
+    }
+
+    #[must_use]
+    pub fn layer_weight_index(&self, name: &str) -> Option<usize> {
+        self.weight_indices.get(name).copied()
+    }
+
+    #[must_use]
+    pub fn layer_weight_name(&self, index: usize) -> Option<&str> {
+        self.weights.get(index).map(String::as_str)
+    }
+
+    #[must_use]
+    pub fn n_layers(&self) -> usize {
+        self.layers.len()
+    }
+
+    #[must_use]
+    pub fn n_layer_weights(&self) -> usize {
+        self.weights.len()
+    }
+
+    /// # Errors
+    /// Returns a `LayerError` if any of the names is not registered.
+    pub fn edge_layer(
+        &self,
+        layer_start: &str,
+        layer_end: &str,
+        layer_weight: &str,
+    ) -> Result<EdgeLayerID, LayerError> {
+        let layer = |name: &str| {
+            self.layer_index(name)
+                .ok_or_else(|| LayerError::UnknownLayerName(name.to_string()))
+        };
+        Ok(EdgeLayerID {
+            layer_start: layer(layer_start)?,
+            layer_end: layer(layer_end)?,
+            layer_weight_index: self
+                .layer_weight_index(layer_weight)
+                .ok_or_else(|| LayerError::UnknownLayerWeightName(layer_weight.to_string()))?,
+        })
+    }
+
+    /// # Errors
+    /// Returns a `LayerError` if any index of `layer` is not registered.
+    pub fn edge_layer_names(&self, layer: &EdgeLayerID) -> Result<NamedEdgeLayer, LayerError> {
+        let name = |index: usize| {
+            self.layer_name(index)
+                .map(str::to_string)
+                .ok_or(LayerError::UnknownLayer(index))
+        };
+        let layers = (name(layer.layer_start)?, name(layer.layer_end)?);
+        let weight = self
+            .layer_weight_name(layer.layer_weight_index)
+            .ok_or(LayerError::UnknownLayerWeight(layer.layer_weight_index))?;
+        Ok((layers, weight.to_string()))
+    }
+
+    /// Human readable name of `layer`, e.g. `calls->mobility:weight`.
+    /// Unregistered layers and layer weights are written as their indices.
+    #[must_use]
+    pub fn edge_layer_label(&self, layer: &EdgeLayerID) -> String {
+        let layer_name = |index: usize| {
+            self.layer_name(index)
+                .map_or_else(|| index.to_string(), str::to_string)
+        };
+        let weight_name = self
+            .layer_weight_name(layer.layer_weight_index)
+            .map_or_else(|| layer.layer_weight_index.to_string(), str::to_string);
+        format!(
+            "{}->{}:{weight_name}",
+            layer_name(layer.layer_start),
+            layer_name(layer.layer_end)
+        )
+    }
+
+    /// Converts a named edge list into the tuple format used by the rest of
+    /// the crate, registering any new layer and weight names along the way.
+    pub fn intern_edge_list(
+        &mut self,
+        edges: &[NamedLayerTupleEdge],
+    ) -> Vec<(usize, usize, usize, usize, usize, f32)> {
+        edges
+            .iter()
+            .map(
+                |(source, target, layer_start, layer_end, layer_weight, weight)| {
+                    (
+                        *source,
+                        *target,
+                        self.add_layer(layer_start),
+                        self.add_layer(layer_end),
+                        self.add_layer_weight(layer_weight),
+                        *weight,
+                    )
+                },
+            )
+            .collect()
+    }
+
+    /// Checks that every `EdgeLayerID` in `edges` refers to registered layers
+    /// and a registered layer weight.
+    ///
+    /// # Errors
+    /// Returns the first unregistered index that is found.
+    pub fn validate_edge_list(
+        &self,
+        edges: &[(usize, usize, usize, usize, usize, f32)],
+    ) -> Result<(), LayerError> {
+        for (_, _, layer_start, layer_end, layer_weight_index, _) in edges {
+            self.edge_layer_names(&EdgeLayerID {
+                layer_start: *layer_start,
+                layer_end: *layer_end,
+                layer_weight_index: *layer_weight_index,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Checks that every `EdgeLayerID` in `graph` refers to registered layers
+    /// and a registered layer weight.
+    ///
+    /// # Errors
+    /// Returns the first unregistered index that is found.
+    pub fn validate_graph(&self, graph: &impl MultidistanceGraph) -> Result<(), LayerError> {
+        for node in graph.nodes() {
+            for (_, weight) in graph.neighbor_edges(&node) {
+                self.named_distance(&weight)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// # Errors
+    /// Returns a `LayerError` if `distance` uses an unregistered layer or
+    /// layer weight.
+    pub fn named_distance(
+        &self,
+        distance: &MultiDistance,
+    ) -> Result<HashMap<NamedEdgeLayer, f32>, LayerError> {
+        distance
+            .total
+            .iter()
+            .map(|(layer, weight)| Ok((self.edge_layer_names(layer)?, *weight)))
+            .collect()
+    }
+
+    /// Replaces the `EdgeLayerID`s of every distance in a closure or backbone
+    /// by layer names.
+    ///
+    /// # Errors
+    /// Returns a `LayerError` if any distance uses an unregistered layer or
+    /// layer weight.
+    #[allow(clippy::type_complexity)]
+    pub fn name_nested_map(
+        &self,
+        map: &HashMap<NodeID, HashMap<NodeID, Vec<MultiDistance>>>,
+    ) -> Result<HashMap<NodeID, HashMap<NodeID, Vec<HashMap<NamedEdgeLayer, f32>>>>, LayerError>
+    {
+        map.iter()
+            .map(|(source, targets)| {
+                let targets = targets
+                    .iter()
+                    .map(|(target, distances)| {
+                        let named = distances
+                            .iter()
+                            .map(|d| self.named_distance(d))
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Ok((*target, named))
+                    })
+                    .collect::<Result<HashMap<_, _>, LayerError>>()?;
+                Ok((*source, targets))
+            })
+            .collect()
+    }
+}
+
+fn get_or_insert(
+    names: &mut Vec<String>,
+    indices: &mut HashMap<String, usize>,
+    name: &str,
+) -> usize {
+    if let Some(index) = indices.get(name) {
+        return *index;
+    }
+    let index = names.len();
+    names.push(name.to_string());
+    indices.insert(name.to_string(), index);
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MultidistanceGraphHashmap;
+
+    #[test]
+    fn test_layer_names() {
+        let mut registry = LayerRegistry::from_layer_names(&["calls", "mobility"]);
+        let edges = registry.intern_edge_list(&[
+            (0, 1, "calls".into(), "calls".into(), "weight".into(), 1.0),
+            (
+                1,
+                2,
+                "calls".into(),
+                "mobility".into(),
+                "weight".into(),
+                2.0,
+            ),
+            (
+                2,
+                0,
+                "mobility".into(),
+                "mobility".into(),
+                "time".into(),
+                3.0,
+            ),
+        ]);
+
+        assert_eq!(
+            edges,
+            vec![
+                (0, 1, 0, 0, 0, 1.0),
+                (1, 2, 0, 1, 0, 2.0),
+                (2, 0, 1, 1, 1, 3.0)
+            ]
+        );
+        assert_eq!(registry.n_layers(), 2);
+        assert_eq!(registry.layer_weight_name(1), Some("time"));
+        assert_eq!(
+            registry.edge_layer("calls", "mobility", "weight"),
+            Ok(EdgeLayerID {
+                layer_start: 0,
+                layer_end: 1,
+                layer_weight_index: 0,
+            })
+        );
+        assert_eq!(
+            registry.edge_layer("calls", "bus", "weight"),
+            Err(LayerError::UnknownLayerName("bus".to_string()))
+        );
+
+        let distance = MultiDistance::from_tuple(0, 1, 0, 2.0);
+        assert_eq!(
+            registry.named_distance(&distance),
+            Ok(HashMap::from([(
+                (
+                    ("calls".to_string(), "mobility".to_string()),
+                    "weight".to_string()
+                ),
+                2.0
+            )]))
+        );
+
+        let graph = MultidistanceGraphHashmap::from_tuple_edge_list(&edges);
+        assert_eq!(registry.validate_graph(&graph), Ok(()));
+        assert_eq!(
+            LayerRegistry::from_layer_names(&["calls"]).validate_graph(&graph),
+            Err(LayerError::UnknownLayer(1))
+        );
+        assert_eq!(
+            registry.validate_edge_list(&[(0, 1, 0, 0, 2, 1.0)]),
+            Err(LayerError::UnknownLayerWeight(2))
+        );
+    }
+}
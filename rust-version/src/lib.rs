@@ -1,76 +1,46 @@
 mod bfs_tools;
+mod binary;
 mod closure;
+#[cfg(feature = "arrow")]
+mod columnar;
 mod direct_backbone;
+mod duplicates;
+mod edgelist;
+mod flatten;
+#[cfg(feature = "graphml")]
+mod graphml;
+mod layer_backbone;
+mod layer_registry;
 mod multidistance;
 mod multigraph;
+mod multiplex;
+mod node_registry;
+#[cfg(feature = "python")]
+mod python;
 mod shortest_paths;
 
 use std::collections::HashMap;
+use std::hash::Hash;
 
 pub use bfs_tools::*;
+pub use binary::*;
 pub use closure::*;
+#[cfg(feature = "arrow")]
+pub use columnar::*;
 pub use direct_backbone::*;
+pub use duplicates::*;
+pub use edgelist::*;
+pub use flatten::*;
+#[cfg(feature = "graphml")]
+pub use graphml::*;
+pub use layer_backbone::*;
+pub use layer_registry::*;
 pub use multidistance::*;
 pub use multigraph::*;
+pub use multiplex::*;
+pub use node_registry::*;
 pub use shortest_paths::*;
 
-use pyo3::prelude::*;
-
-#[pymodule]
-fn backbone(_py: Python, m: &PyModule) -> PyResult<()> {
-    m.add_function(wrap_pyfunction!(distance_closure_py, m)?)?;
-    m.add_function(wrap_pyfunction!(backbone_py, m)?)?;
-    m.add_function(wrap_pyfunction!(structural_backbone_simas, m)?)?;
-    m.add_function(wrap_pyfunction!(structural_backbone_costa, m)?)?;
-    m.add_function(wrap_pyfunction!(structural_backbone_naive, m)?)?;
-
-    Ok(())
-}
-
-#[pyfunction]
-#[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
-fn distance_closure_py(
-    edges: Vec<(usize, usize, usize, usize, usize, f32)>,
-) -> MultidistanceClosure {
-    distance_closure(&edges)
-}
-
-#[pyfunction]
-#[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
-fn backbone_py(edges: Vec<(usize, usize, usize, usize, usize, f32)>) -> MultilayerBackbone {
-    multilayer_backbone(&edges)
-}
-
-#[pyfunction]
-#[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
-fn structural_backbone_simas(
-    edges: Vec<(usize, usize, usize, usize, usize, f32)>,
-) -> HashMap<NodeID, HashMap<NodeID, MultiDistance>> {
-    let mut graph = MultidistanceGraphHashmap::from_tuple_edge_list(&edges);
-    fast_backbone_simas(&mut graph);
-    graph.edges
-}
-
-#[pyfunction]
-#[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
-fn structural_backbone_costa(
-    edges: Vec<(usize, usize, usize, usize, usize, f32)>,
-) -> HashMap<NodeID, HashMap<NodeID, MultiDistance>> {
-    let mut graph = MultidistanceGraphHashmap::from_tuple_edge_list(&edges);
-    fast_backbone_costa(&mut graph);
-    graph.edges
-}
-
-#[pyfunction]
-#[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
-fn structural_backbone_naive(
-    edges: Vec<(usize, usize, usize, usize, usize, f32)>,
-) -> HashMap<NodeID, HashMap<NodeID, MultiDistance>> {
-    let mut graph = MultidistanceGraphHashmap::from_tuple_edge_list(&edges);
-    structural_backbone(&mut graph, None);
-    graph.edges
-}
-
 /// The function `distance_closure` takes a list of edges and returns a
 /// multidistance closure.
 ///
@@ -88,7 +58,7 @@ fn structural_backbone_naive(
 pub fn distance_closure(
     edges: &[(usize, usize, usize, usize, usize, f32)],
 ) -> MultidistanceClosure {
-    let graph = MultidistanceGraphHashmap::from_tuple_edge_list(edges);
+    let graph = MultidistanceMultigraphHashmap::from_tuple_edge_list(edges);
     multidistance_closure(&graph)
 }
 
@@ -111,7 +81,7 @@ pub fn distance_closure(
 pub fn multilayer_backbone(
     edges: &[(usize, usize, usize, usize, usize, f32)],
 ) -> MultilayerBackbone {
-    let graph = MultidistanceGraphHashmap::from_tuple_edge_list(edges);
+    let graph = MultidistanceMultigraphHashmap::from_tuple_edge_list(edges);
     let closure = multidistance_closure(&graph);
 
     let mut backbone = HashMap::new();
@@ -140,3 +110,129 @@ pub fn multilayer_backbone(
 
     backbone
 }
+
+/// Classifies every edge of a multigraph separately. Node pairs may be
+/// connected by several edges (e.g. one per layer); the result lists each
+/// metric edge as `(source, target, weight)`.
+#[must_use]
+pub fn multigraph_backbone(
+    edges: &[(usize, usize, usize, usize, usize, f32)],
+) -> Vec<(NodeID, NodeID, MultiDistance)> {
+    let mut graph = MultidistanceMultigraphHashmap::from_tuple_edge_list(edges);
+    fast_backbone_costa_parallel(&mut graph);
+    graph
+        .edge_list()
+        .map(|(source, target, weight)| (source, target, weight.clone()))
+        .collect()
+}
+
+/// Same as [`multigraph_backbone`], but returns whether each row of `edges`
+/// is a metric edge, in the order of `edges`.
+#[must_use]
+pub fn multigraph_metric_mask(edges: &[(usize, usize, usize, usize, usize, f32)]) -> Vec<bool> {
+    let mut graph = MultidistanceMultigraphHashmap::from_tuple_edge_list(edges);
+    fast_backbone_costa_parallel(&mut graph);
+    edges
+        .iter()
+        .map(
+            |&(source, target, layer_start, layer_end, layer_weight_index, weight)| {
+                let weight =
+                    MultiDistance::from_tuple(layer_start, layer_end, layer_weight_index, weight);
+                graph
+                    .parallel_edges(NodeID(source), NodeID(target))
+                    .contains(&weight)
+            },
+        )
+        .collect()
+}
+
+/// Same as [`distance_closure`], but the edge endpoints are arbitrary labels
+/// which are also used as the keys of the returned closure.
+#[must_use]
+pub fn distance_closure_labeled<L>(
+    edges: &[LabeledTupleEdge<L>],
+) -> HashMap<L, HashMap<L, Vec<MultiDistance>>>
+where
+    L: Hash + Eq + Clone,
+{
+    let mut registry = NodeRegistry::new();
+    let edges = registry.intern_edge_list(edges);
+    registry.label_nested_map(&distance_closure(&edges))
+}
+
+/// Same as [`multilayer_backbone`], but the edge endpoints are arbitrary
+/// labels which are also used as the keys of the returned backbone.
+///
+/// # Panics
+/// * Will panic if the computed closure does not contain an entry for a direct edge.
+#[must_use]
+pub fn multilayer_backbone_labeled<L>(
+    edges: &[LabeledTupleEdge<L>],
+) -> HashMap<L, HashMap<L, Vec<MultiDistance>>>
+where
+    L: Hash + Eq + Clone,
+{
+    let mut registry = NodeRegistry::new();
+    let edges = registry.intern_edge_list(edges);
+    registry.label_nested_map(&multilayer_backbone(&edges))
+}
+
+/// Same as [`distance_closure`], but layers and layer weights are given by
+/// name. The returned `LayerRegistry` maps the names to the indices used in
+/// the closure.
+#[must_use]
+pub fn distance_closure_named(
+    edges: &[NamedLayerTupleEdge],
+) -> (MultidistanceClosure, LayerRegistry) {
+    let mut layers = LayerRegistry::new();
+    let edges = layers.intern_edge_list(edges);
+    (distance_closure(&edges), layers)
+}
+
+/// Same as [`multilayer_backbone`], but layers and layer weights are given by
+/// name. The returned `LayerRegistry` maps the names to the indices used in
+/// the backbone.
+///
+/// # Panics
+/// * Will panic if the computed closure does not contain an entry for a direct edge.
+#[must_use]
+pub fn multilayer_backbone_named(
+    edges: &[NamedLayerTupleEdge],
+) -> (MultilayerBackbone, LayerRegistry) {
+    let mut layers = LayerRegistry::new();
+    let edges = layers.intern_edge_list(edges);
+    (multilayer_backbone(&edges), layers)
+}
+
+/// Same as [`distance_closure`], but the edges are undirected. Only the upper
+/// triangle of the closure (`closure[u][v]` with `u < v`) is returned; the
+/// search work is that of the symmetrized directed graph (see
+/// [`multidistance_closure_upper_triangle`]).
+#[must_use]
+pub fn distance_closure_undirected(
+    edges: &[(usize, usize, usize, usize, usize, f32)],
+) -> MultidistanceClosure {
+    let graph = UndirectedMultidistanceGraphHashmap::from_tuple_edge_list(edges);
+    multidistance_closure_upper_triangle(&graph)
+}
+
+/// Same as [`multilayer_backbone`], but the edges are undirected. Every
+/// backbone edge appears once, as `backbone[u][v]` with `u <= v`.
+#[must_use]
+pub fn multilayer_backbone_undirected(
+    edges: &[(usize, usize, usize, usize, usize, f32)],
+) -> MultilayerBackbone {
+    let graph = UndirectedMultidistanceGraphHashmap::from_tuple_edge_list(edges);
+    let closure = multidistance_closure_upper_triangle(&graph);
+
+    let mut backbone: MultilayerBackbone = HashMap::new();
+    for (u, v, weight) in graph.undirected_edges() {
+        // self-loops never shorten a path, and are not part of the closure
+        if let Some(mins) = closure.get(&u).and_then(|targets| targets.get(&v)) {
+            if mins.contains(weight) {
+                backbone.entry(u).or_default().insert(v, mins.clone());
+            }
+        }
+    }
+    backbone
+}
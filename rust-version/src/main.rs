@@ -0,0 +1,541 @@
+//! `backbone`: computes closures and backbones of multilayer graphs stored in
+//! edge list or GraphML files.
+
+use backbone::{
+    closure_table, fast_backbone_costa, fast_backbone_costa_parallel, fast_backbone_simas,
+    multidistance_closure, read_edge_list_file, read_graphml, structural_backbone,
+    write_backbone_graphml, write_closure, write_graph, DuplicatePolicy, EdgeListFormat,
+    GraphmlFormat, GraphmlLayers, LayerColumns, LayerRegistry, MultiDistance, MultidistanceClosure,
+    MultidistanceGraph, MultidistanceMultigraphHashmap, NodeID, NodeRegistry,
+};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[derive(Parser, Debug)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Pareto shortest distances between all node pairs.
+    Closure {
+        #[command(flatten)]
+        input: InputArgs,
+        #[command(flatten)]
+        output: OutputArgs,
+        #[arg(long, value_enum, default_value_t = ClosureFormat::Tsv)]
+        format: ClosureFormat,
+    },
+    /// The edges that are shortest paths between their endpoints.
+    Backbone {
+        #[command(flatten)]
+        input: InputArgs,
+        #[command(flatten)]
+        output: OutputArgs,
+        #[arg(long, value_enum, default_value_t = Algorithm::CostaParallel)]
+        algorithm: Algorithm,
+        #[arg(long, value_enum, default_value_t = GraphFormat::Tsv)]
+        format: GraphFormat,
+    },
+    /// Every edge, marked as metric (in the backbone) or semimetric.
+    Classify {
+        #[command(flatten)]
+        input: InputArgs,
+        #[command(flatten)]
+        output: OutputArgs,
+        #[arg(long, value_enum, default_value_t = ClassifyFormat::Tsv)]
+        format: ClassifyFormat,
+    },
+    /// Sizes of the graph and its backbone, in total and per layer.
+    Stats {
+        #[command(flatten)]
+        input: InputArgs,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+}
+
+#[derive(Args, Debug)]
+struct InputArgs {
+    /// Input files. Files that share node labels or layer names are merged.
+    #[arg(required = true)]
+    inputs: Vec<PathBuf>,
+    #[arg(long, value_enum, default_value_t = InputFormat::Auto)]
+    input_format: InputFormat,
+    /// Layer of every edge, unless layers are read from columns or attributes.
+    #[arg(long, default_value = "0")]
+    layer: String,
+    /// Name the layer of every edge after the stem of its file.
+    #[arg(long, conflicts_with = "layer")]
+    layer_from_file: bool,
+    /// Merges repeated edges of an edge list: `error`, `first`, `last`, `min`,
+    /// `sum` or `count`.
+    #[arg(long, default_value = "last")]
+    duplicates: DuplicatePolicy,
+
+    /// Edge list field separator, e.g. `,` or `tab` [default: whitespace].
+    #[arg(long, help_heading = "Edge lists")]
+    delimiter: Option<String>,
+    #[arg(long, default_value_t = 0, help_heading = "Edge lists")]
+    source_column: usize,
+    #[arg(long, default_value_t = 1, help_heading = "Edge lists")]
+    target_column: usize,
+    /// Column with the layer name (the source layer for interlayer edges).
+    #[arg(long, help_heading = "Edge lists")]
+    layer_column: Option<usize>,
+    /// Column with the target layer name.
+    #[arg(long, requires = "layer_column", help_heading = "Edge lists")]
+    layer_end_column: Option<usize>,
+    /// Column with the edge weight [default: every row has weight 1].
+    #[arg(long, help_heading = "Edge lists")]
+    weight_column: Option<usize>,
+    #[arg(long, default_value = "#", help_heading = "Edge lists")]
+    comment: String,
+    /// Skip the first line that is not a comment.
+    #[arg(long, help_heading = "Edge lists")]
+    header: bool,
+
+    #[arg(long, default_value = "distance", help_heading = "GraphML")]
+    weight_attribute: String,
+    /// Edge attribute with the layer name (the source layer for interlayer
+    /// edges).
+    #[arg(long, help_heading = "GraphML")]
+    layer_attribute: Option<String>,
+    /// Edge attribute with the target layer name.
+    #[arg(long, requires = "layer_attribute", help_heading = "GraphML")]
+    layer_end_attribute: Option<String>,
+    /// Node attribute with the layer of the node.
+    #[arg(long, conflicts_with = "layer_attribute", help_heading = "GraphML")]
+    node_layer_attribute: Option<String>,
+}
+
+#[derive(Args, Debug)]
+struct OutputArgs {
+    /// Output file [default: standard output].
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum InputFormat {
+    /// GraphML for `.graphml` files, edge lists otherwise.
+    Auto,
+    Edgelist,
+    Graphml,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Algorithm {
+    Costa,
+    CostaParallel,
+    Simas,
+    /// A bounded search for every edge.
+    Naive,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ClosureFormat {
+    /// One row per Pareto distance, one column per layer.
+    Tsv,
+    /// The binary format of `backbone::write_closure`.
+    Binary,
+    #[cfg(feature = "arrow")]
+    Arrow,
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum GraphFormat {
+    Tsv,
+    /// The binary format of `backbone::write_graph`.
+    Binary,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ClassifyFormat {
+    Tsv,
+    /// GraphML with the attributes of `backbone::write_backbone_graphml`.
+    Graphml,
+}
+
+/// A graph read from the input files, with the registries for its labels.
+struct Input {
+    edges: Vec<(usize, usize, usize, usize, usize, f32)>,
+    graph: MultidistanceMultigraphHashmap,
+    nodes: NodeRegistry<String>,
+    layers: LayerRegistry,
+}
+
+fn read_input(args: &InputArgs) -> Result<Input, Box<dyn Error>> {
+    let mut nodes = NodeRegistry::new();
+    let mut layers = LayerRegistry::new();
+    let mut edges = Vec::new();
+
+    for path in &args.inputs {
+        let layer = if args.layer_from_file {
+            path.file_stem()
+                .map_or_else(|| args.layer.clone(), |s| s.to_string_lossy().into_owned())
+        } else {
+            args.layer.clone()
+        };
+        let is_graphml = match args.input_format {
+            InputFormat::Auto => path.extension().is_some_and(|e| e == "graphml"),
+            InputFormat::Edgelist => false,
+            InputFormat::Graphml => true,
+        };
+
+        if is_graphml {
+            let format = GraphmlFormat {
+                weight_attribute: args.weight_attribute.clone(),
+                layers: match (
+                    &args.layer_attribute,
+                    &args.layer_end_attribute,
+                    &args.node_layer_attribute,
+                ) {
+                    (Some(start), Some(end), _) => {
+                        GraphmlLayers::EdgeAttributes(start.clone(), end.clone())
+                    }
+                    (Some(name), None, _) => GraphmlLayers::EdgeAttribute(name.clone()),
+                    (None, _, Some(name)) => GraphmlLayers::NodeAttribute(name.clone()),
+                    (None, _, None) => GraphmlLayers::Fixed(layer),
+                },
+                ..GraphmlFormat::default()
+            };
+            let file = BufReader::new(File::open(path).map_err(|e| in_file(path, e))?);
+            edges.extend(
+                read_graphml(file, &format, &mut nodes, &mut layers)
+                    .map_err(|e| in_file(path, e))?,
+            );
+        } else {
+            let format = EdgeListFormat {
+                delimiter: args.delimiter.as_deref().map(parse_delimiter).transpose()?,
+                source_column: args.source_column,
+                target_column: args.target_column,
+                layers: match (args.layer_column, args.layer_end_column) {
+                    (Some(start), Some(end)) => LayerColumns::Pair(start, end),
+                    (Some(column), None) => LayerColumns::Single(column),
+                    (None, _) => LayerColumns::Fixed(layer),
+                },
+                weight_column: args.weight_column,
+                comment_prefix: Some(args.comment.clone()).filter(|c| !c.is_empty()),
+                has_header: args.header,
+                duplicates: args.duplicates,
+                ..EdgeListFormat::default()
+            };
+            let (file_edges, summary) = read_edge_list_file(path, &format, &mut nodes, &mut layers)
+                .map_err(|e| in_file(path, e))?;
+            if summary.n_merged() > 0 {
+                eprintln!(
+                    "backbone: {}: merged {} duplicate rows",
+                    path.display(),
+                    summary.n_merged()
+                );
+            }
+            edges.extend(file_edges);
+        }
+    }
+
+    Ok(Input {
+        graph: MultidistanceMultigraphHashmap::from_tuple_edge_list(&edges),
+        edges,
+        nodes,
+        layers,
+    })
+}
+
+fn in_file(path: &Path, e: impl fmt::Display) -> String {
+    format!("{}: {e}", path.display())
+}
+
+fn parse_delimiter(delimiter: &str) -> Result<char, String> {
+    match delimiter {
+        "tab" | "\\t" => Ok('\t'),
+        "comma" => Ok(','),
+        "space" => Ok(' '),
+        _ => {
+            let mut chars = delimiter.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Ok(c),
+                _ => Err(format!(
+                    "delimiter must be a single character, got {delimiter:?}"
+                )),
+            }
+        }
+    }
+}
+
+fn open_output(args: &OutputArgs) -> io::Result<Box<dyn Write>> {
+    Ok(match &args.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    })
+}
+
+fn label(nodes: &NodeRegistry<String>, node: NodeID) -> &str {
+    nodes.label(node).map_or("?", String::as_str)
+}
+
+/// Layer labels of `distance`, separated by commas, and its total weight.
+fn describe(distance: &MultiDistance, layers: &LayerRegistry) -> (String, f32) {
+    let mut labels: Vec<String> = distance
+        .total
+        .keys()
+        .map(|l| layers.edge_layer_label(l))
+        .collect();
+    labels.sort_unstable();
+    (labels.join(","), distance.total.values().sum())
+}
+
+/// Every edge as `(source, target, weight)`, sorted by node pair.
+fn sorted_edges(graph: &MultidistanceMultigraphHashmap) -> Vec<(NodeID, NodeID, &MultiDistance)> {
+    let mut edges: Vec<_> = graph.edge_list().collect();
+    edges.sort_by_key(|(source, target, _)| (*source, *target));
+    edges
+}
+
+fn is_metric(
+    closure: &MultidistanceClosure,
+    source: NodeID,
+    target: NodeID,
+    weight: &MultiDistance,
+) -> bool {
+    closure
+        .get(&source)
+        .and_then(|targets| targets.get(&target))
+        .is_some_and(|distances| distances.contains(weight))
+}
+
+fn run_closure(
+    input: &Input,
+    out: &mut dyn Write,
+    format: ClosureFormat,
+) -> Result<(), Box<dyn Error>> {
+    let closure = multidistance_closure(&input.graph);
+    match format {
+        ClosureFormat::Tsv => {
+            let (layer_ids, rows) = closure_table(&closure);
+            write!(out, "source\ttarget\tpareto_index")?;
+            for layer in &layer_ids {
+                write!(out, "\t{}", input.layers.edge_layer_label(layer))?;
+            }
+            writeln!(out)?;
+
+            for (source, target, i, distance) in rows {
+                write!(
+                    out,
+                    "{}\t{}\t{i}",
+                    label(&input.nodes, source),
+                    label(&input.nodes, target)
+                )?;
+                for layer in &layer_ids {
+                    write!(
+                        out,
+                        "\t{}",
+                        distance.total.get(layer).copied().unwrap_or(0.0)
+                    )?;
+                }
+                writeln!(out)?;
+            }
+        }
+        ClosureFormat::Binary => write_closure(out, &closure, &input.layers)?,
+        #[cfg(feature = "arrow")]
+        ClosureFormat::Arrow => backbone::write_closure_ipc(out, &closure, &input.layers)?,
+        #[cfg(feature = "parquet")]
+        ClosureFormat::Parquet => {
+            // the Parquet writer needs a `Send` sink
+            let mut buffer = Vec::new();
+            backbone::write_closure_parquet(&mut buffer, &closure, &input.layers)?;
+            out.write_all(&buffer)?;
+        }
+    }
+    Ok(())
+}
+
+fn run_backbone(
+    mut input: Input,
+    out: &mut dyn Write,
+    algorithm: Algorithm,
+    format: GraphFormat,
+) -> Result<(), Box<dyn Error>> {
+    match algorithm {
+        Algorithm::Costa => fast_backbone_costa(&mut input.graph),
+        Algorithm::CostaParallel => fast_backbone_costa_parallel(&mut input.graph),
+        Algorithm::Simas => fast_backbone_simas(&mut input.graph),
+        Algorithm::Naive => structural_backbone(&mut input.graph, None),
+    }
+    match format {
+        GraphFormat::Tsv => {
+            writeln!(out, "source\ttarget\tlayer\tweight")?;
+            for (source, target, weight) in sorted_edges(&input.graph) {
+                let (layer, weight) = describe(weight, &input.layers);
+                writeln!(
+                    out,
+                    "{}\t{}\t{layer}\t{weight}",
+                    label(&input.nodes, source),
+                    label(&input.nodes, target)
+                )?;
+            }
+        }
+        GraphFormat::Binary => write_graph(out, &input.graph, &input.layers)?,
+    }
+    Ok(())
+}
+
+fn run_classify(
+    input: &Input,
+    out: &mut dyn Write,
+    format: ClassifyFormat,
+) -> Result<(), Box<dyn Error>> {
+    let closure = multidistance_closure(&input.graph);
+    match format {
+        ClassifyFormat::Tsv => {
+            writeln!(out, "source\ttarget\tlayer\tweight\tmetric")?;
+            for (source, target, weight) in sorted_edges(&input.graph) {
+                let metric = is_metric(&closure, source, target, weight);
+                let (layer, weight) = describe(weight, &input.layers);
+                writeln!(
+                    out,
+                    "{}\t{}\t{layer}\t{weight}\t{metric}",
+                    label(&input.nodes, source),
+                    label(&input.nodes, target)
+                )?;
+            }
+        }
+        ClassifyFormat::Graphml => {
+            write_backbone_graphml(out, &input.edges, &closure, &input.nodes, &input.layers)?;
+        }
+    }
+    Ok(())
+}
+
+fn run_stats(input: &Input, out: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+    let mut backbone = input.graph.clone();
+    fast_backbone_costa_parallel(&mut backbone);
+
+    // edges per layer label, zero-weight edges under ""
+    let count = |graph: &MultidistanceMultigraphHashmap| {
+        let mut counts = std::collections::BTreeMap::<String, usize>::new();
+        for (_, _, weight) in graph.edge_list() {
+            *counts.entry(describe(weight, &input.layers).0).or_default() += 1;
+        }
+        counts
+    };
+    let n_edges = input.graph.edge_list().count();
+    let n_metric = backbone.edge_list().count();
+    #[allow(clippy::cast_precision_loss)] // only for display
+    let fraction = |metric: usize, total: usize| metric as f64 / total.max(1) as f64;
+
+    writeln!(out, "nodes\t{}", input.graph.nodes().len())?;
+    writeln!(out, "layers\t{}", input.layers.n_layers())?;
+    writeln!(out, "edges\t{n_edges}")?;
+    writeln!(out, "metric_edges\t{n_metric}")?;
+    writeln!(out, "metric_fraction\t{:.6}", fraction(n_metric, n_edges))?;
+    let metric_counts = count(&backbone);
+    for (layer, total) in count(&input.graph) {
+        let metric = metric_counts.get(&layer).copied().unwrap_or(0);
+        writeln!(
+            out,
+            "layer\t{layer}\t{total}\t{metric}\t{:.6}",
+            fraction(metric, total)
+        )?;
+    }
+    Ok(())
+}
+
+fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
+    match cli.command {
+        Command::Closure {
+            input,
+            output,
+            format,
+        } => run_closure(&read_input(&input)?, &mut open_output(&output)?, format),
+        Command::Backbone {
+            input,
+            output,
+            algorithm,
+            format,
+        } => run_backbone(
+            read_input(&input)?,
+            &mut open_output(&output)?,
+            algorithm,
+            format,
+        ),
+        Command::Classify {
+            input,
+            output,
+            format,
+        } => run_classify(&read_input(&input)?, &mut open_output(&output)?, format),
+        Command::Stats { input, output } => {
+            run_stats(&read_input(&input)?, &mut open_output(&output)?)
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        // e.g. the output was piped into `head`
+        Err(e)
+            if e.downcast_ref::<io::Error>()
+                .is_some_and(|e| e.kind() == io::ErrorKind::BrokenPipe) =>
+        {
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("backbone: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli() {
+        Cli::command().debug_assert();
+
+        let dir = std::env::temp_dir().join(format!("backbone-cli-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("calls.tsv");
+        std::fs::write(&path, "a\tb\t1\nb\tc\t1\na\tc\t3\n").unwrap();
+        let path = path.to_str().unwrap();
+
+        let cli = Cli::parse_from([
+            "backbone",
+            "classify",
+            path,
+            "--delimiter",
+            "tab",
+            "--weight-column",
+            "2",
+            "--layer-from-file",
+        ]);
+        let Command::Classify { input, .. } = cli.command else {
+            panic!("expected classify");
+        };
+        let input = read_input(&input).unwrap();
+        let mut out = Vec::new();
+        run_classify(&input, &mut out, ClassifyFormat::Tsv).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "source\ttarget\tlayer\tweight\tmetric\n\
+             a\tb\tcalls->calls:weight\t1\ttrue\n\
+             a\tc\tcalls->calls:weight\t3\tfalse\n\
+             b\tc\tcalls->calls:weight\t1\ttrue\n"
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,4 +1,3 @@
-use pyo3::prelude::*;
 use rustc_hash::{FxHashMap, FxHashSet};
 use std::fmt;
 use std::ops::Add;
@@ -7,21 +6,13 @@ use std::ops::Add;
 // use identity_hash::IdentityHasher;
 // use std::hash::BuildHasherDefault;
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, PartialOrd, Ord)]
 pub struct NodeID(pub usize);
 impl fmt::Display for NodeID {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{self:?}")
     }
 }
-impl IntoPy<PyObject> for NodeID {
-    fn into_py(self, py: Python<'_>) -> PyObject {
-        let NodeID(node_id_val) = self;
-        // PyObject::from(PyInt::new(py, node_id_val))
-        node_id_val.into_py(py)
-    }
-}
-
 #[must_use]
 pub fn multimin(dists: &[MultiDistance]) -> Vec<MultiDistance> {
     let mut minlist = Vec::new();
@@ -48,12 +39,6 @@ pub struct EdgeLayerID {
     pub layer_weight_index: usize,
 }
 
-impl IntoPy<PyObject> for EdgeLayerID {
-    fn into_py(self, py: Python<'_>) -> PyObject {
-        ((self.layer_start, self.layer_end), self.layer_weight_index).into_py(py)
-    }
-}
-
 // type MultiDistanceMap = HashMap<EdgeLayerID, f32, BuildHasherDefault<IdentityHasher<usize>>>;
 // type MultiDistanceMap = HashMap<EdgeLayerID, f32>;
 type MultiDistanceMap = FxHashMap<EdgeLayerID, f32>;
@@ -113,12 +98,6 @@ impl MultiDistance {
     }
 }
 
-impl IntoPy<PyObject> for MultiDistance {
-    fn into_py(self, py: Python<'_>) -> PyObject {
-        self.total.into_py(py)
-    }
-}
-
 impl Eq for MultiDistance {}
 
 impl Add for MultiDistance {
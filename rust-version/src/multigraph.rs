@@ -1,13 +1,48 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::multidistance::{MultiDistance, NodeID};
+use crate::node_registry::{LabeledTupleEdge, NodeRegistry};
+use std::hash::Hash;
 
 pub trait MultidistanceGraph {
     fn nodes(&self) -> Vec<NodeID>;
+    /// Adds `node` without any edges, if it is not in the graph yet.
+    ///
+    /// The default does nothing, for graphs whose nodes are only the endpoints
+    /// of their edges; such graphs lose isolated nodes, e.g. when read with
+    /// `read_graph`.
+    fn add_node(&mut self, node: NodeID) {
+        let _ = node;
+    }
     fn add_edge(&mut self, from: NodeID, to: NodeID, weight: MultiDistance);
     fn remove_edge(&mut self, from: NodeID, to: NodeID);
     fn neighbor_edges(&self, node: &NodeID) -> Vec<(NodeID, MultiDistance)>;
     fn edge_weight(&self, from: NodeID, to: NodeID) -> Option<&MultiDistance>;
+
+    /// The edges into `node`, as `(source, weight)`.
+    ///
+    /// The default scans the edges of every node, which takes time linear in
+    /// the size of the graph; graphs that keep track of their incoming edges
+    /// override it.
+    fn in_neighbor_edges(&self, node: &NodeID) -> Vec<(NodeID, MultiDistance)> {
+        self.nodes()
+            .into_iter()
+            .flat_map(|source| {
+                self.neighbor_edges(&source)
+                    .into_iter()
+                    .filter(|(target, _)| target == node)
+                    .map(move |(_, weight)| (source, weight))
+            })
+            .collect()
+    }
+
+    /// Removes the edge from `from` to `to` only if it has weight `weight`.
+    /// Graphs with parallel edges remove just the matching edge.
+    fn remove_edge_with_weight(&mut self, from: NodeID, to: NodeID, weight: &MultiDistance) {
+        if self.edge_weight(from, to) == Some(weight) {
+            self.remove_edge(from, to);
+        }
+    }
 }
 
 pub trait ClosureGraph {
@@ -20,16 +55,29 @@ pub trait ClosureGraph {
     fn edge_weight(&self, from: NodeID, to: NodeID) -> Option<&MultiDistance>;
 }
 
-#[derive(Default, Clone, PartialEq, Eq)]
+#[derive(Default, Clone)]
 pub struct MultidistanceGraphHashmap {
     pub(crate) edges: HashMap<NodeID, HashMap<NodeID, MultiDistance>>,
+    /// The sources of the edges into every node, whose weights are looked up
+    /// in `edges`. This costs one node id per edge on top of `edges`.
+    pub(crate) in_edges: HashMap<NodeID, HashSet<NodeID>>,
 }
 
+/// `in_edges` is derived from `edges`, so only the latter is compared.
+impl PartialEq for MultidistanceGraphHashmap {
+    fn eq(&self, other: &Self) -> bool {
+        self.edges == other.edges
+    }
+}
+
+impl Eq for MultidistanceGraphHashmap {}
+
 impl MultidistanceGraphHashmap {
     #[must_use]
     pub fn new() -> MultidistanceGraphHashmap {
         MultidistanceGraphHashmap {
             edges: HashMap::new(),
+            in_edges: HashMap::new(),
         }
     }
 
@@ -57,6 +105,19 @@ impl MultidistanceGraphHashmap {
         }
         graph
     }
+
+    /// Builds a graph from edges whose endpoints are labels, interning them
+    /// in `registry`.
+    #[must_use]
+    pub fn from_labeled_edge_list<L>(
+        edges: &[LabeledTupleEdge<L>],
+        registry: &mut NodeRegistry<L>,
+    ) -> MultidistanceGraphHashmap
+    where
+        L: Hash + Eq + Clone,
+    {
+        MultidistanceGraphHashmap::from_tuple_edge_list(&registry.intern_edge_list(edges))
+    }
 }
 
 impl MultidistanceGraph for MultidistanceGraphHashmap {
@@ -64,15 +125,23 @@ impl MultidistanceGraph for MultidistanceGraphHashmap {
         self.edges.keys().copied().collect()
     }
 
+    fn add_node(&mut self, node: NodeID) {
+        self.edges.entry(node).or_default();
+    }
+
     fn add_edge(&mut self, from: NodeID, to: NodeID, weight: MultiDistance) {
+        self.in_edges.entry(to).or_default().insert(from);
         self.edges.entry(from).or_default().insert(to, weight);
-        self.edges.entry(to).or_insert(HashMap::default()); // to ensure that sink nodes appear in node list
+        self.edges.entry(to).or_default(); // to ensure that sink nodes appear in node list
     }
 
     fn remove_edge(&mut self, from: NodeID, to: NodeID) {
         if let Some(neighbors) = self.edges.get_mut(&from) {
             neighbors.remove(&to);
         }
+        if let Some(sources) = self.in_edges.get_mut(&to) {
+            sources.remove(&from);
+        }
     }
 
     fn neighbor_edges(&self, node: &NodeID) -> Vec<(NodeID, MultiDistance)> {
@@ -84,7 +153,253 @@ impl MultidistanceGraph for MultidistanceGraphHashmap {
             .collect()
     }
 
+    fn in_neighbor_edges(&self, node: &NodeID) -> Vec<(NodeID, MultiDistance)> {
+        self.in_edges
+            .get(node)
+            .map(|sources| {
+                sources
+                    .iter()
+                    .filter_map(|source| Some((*source, self.edge_weight(*source, *node)?.clone())))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     fn edge_weight(&self, from: NodeID, to: NodeID) -> Option<&MultiDistance> {
         self.edges.get(&from).and_then(|x| x.get(&to))
     }
 }
+
+/// An undirected graph in which every edge is stored once, under the ordered
+/// pair of its endpoints. Both endpoints see the edge as a neighbor edge with
+/// the same weight.
+#[derive(Default, Clone, PartialEq, Eq)]
+pub struct UndirectedMultidistanceGraphHashmap {
+    pub(crate) edges: HashMap<(NodeID, NodeID), MultiDistance>,
+    pub(crate) adjacency: HashMap<NodeID, HashSet<NodeID>>,
+}
+
+fn undirected_key(u: NodeID, v: NodeID) -> (NodeID, NodeID) {
+    if u <= v {
+        (u, v)
+    } else {
+        (v, u)
+    }
+}
+
+impl UndirectedMultidistanceGraphHashmap {
+    #[must_use]
+    pub fn new() -> UndirectedMultidistanceGraphHashmap {
+        UndirectedMultidistanceGraphHashmap::default()
+    }
+
+    /// Builds an undirected graph from a tuple edge list. If both `(u, v)` and
+    /// `(v, u)` are listed, the later one wins.
+    #[must_use]
+    pub fn from_tuple_edge_list(
+        edges: &[(usize, usize, usize, usize, usize, f32)],
+    ) -> UndirectedMultidistanceGraphHashmap {
+        let mut graph = UndirectedMultidistanceGraphHashmap::new();
+        for (edge_from, edge_to, layer_start, layer_end, layer_weight_index, weight) in edges {
+            let edge_weight =
+                MultiDistance::from_tuple(*layer_start, *layer_end, *layer_weight_index, *weight);
+
+            graph.add_edge(NodeID(*edge_from), NodeID(*edge_to), edge_weight);
+        }
+        graph
+    }
+
+    /// Every edge once, as `(u, v, weight)` with `u <= v`.
+    pub fn undirected_edges(&self) -> impl Iterator<Item = (NodeID, NodeID, &MultiDistance)> {
+        self.edges.iter().map(|((u, v), weight)| (*u, *v, weight))
+    }
+}
+
+impl MultidistanceGraph for UndirectedMultidistanceGraphHashmap {
+    fn nodes(&self) -> Vec<NodeID> {
+        self.adjacency.keys().copied().collect()
+    }
+
+    fn add_node(&mut self, node: NodeID) {
+        self.adjacency.entry(node).or_default();
+    }
+
+    fn add_edge(&mut self, from: NodeID, to: NodeID, weight: MultiDistance) {
+        self.edges.insert(undirected_key(from, to), weight);
+        self.adjacency.entry(from).or_default().insert(to);
+        self.adjacency.entry(to).or_default().insert(from);
+    }
+
+    fn remove_edge(&mut self, from: NodeID, to: NodeID) {
+        self.edges.remove(&undirected_key(from, to));
+        if let Some(neighbors) = self.adjacency.get_mut(&from) {
+            neighbors.remove(&to);
+        }
+        if let Some(neighbors) = self.adjacency.get_mut(&to) {
+            neighbors.remove(&from);
+        }
+    }
+
+    fn neighbor_edges(&self, node: &NodeID) -> Vec<(NodeID, MultiDistance)> {
+        self.adjacency
+            .get(node)
+            .map(|neighbors| {
+                neighbors
+                    .iter()
+                    .filter_map(|other| {
+                        self.edges
+                            .get(&undirected_key(*node, *other))
+                            .map(|weight| (*other, weight.clone()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn edge_weight(&self, from: NodeID, to: NodeID) -> Option<&MultiDistance> {
+        self.edges.get(&undirected_key(from, to))
+    }
+
+    /// Every edge is also an edge into `node`.
+    fn in_neighbor_edges(&self, node: &NodeID) -> Vec<(NodeID, MultiDistance)> {
+        self.neighbor_edges(node)
+    }
+}
+
+/// A directed graph that can carry several edges between the same pair of
+/// nodes, e.g. one per layer. Every parallel edge is a separate neighbor edge,
+/// so the backbone algorithms classify each of them on its own. Parallel edges
+/// with equal weights are stored once.
+#[derive(Default, Clone)]
+pub struct MultidistanceMultigraphHashmap {
+    pub(crate) edges: HashMap<NodeID, HashMap<NodeID, Vec<MultiDistance>>>,
+    /// The sources of the edges into every node, whose weights are looked up
+    /// in `edges`. This costs one node id per node pair on top of `edges`.
+    pub(crate) in_edges: HashMap<NodeID, HashSet<NodeID>>,
+}
+
+/// `in_edges` is derived from `edges`, so only the latter is compared.
+impl PartialEq for MultidistanceMultigraphHashmap {
+    fn eq(&self, other: &Self) -> bool {
+        self.edges == other.edges
+    }
+}
+
+impl Eq for MultidistanceMultigraphHashmap {}
+
+impl MultidistanceMultigraphHashmap {
+    #[must_use]
+    pub fn new() -> MultidistanceMultigraphHashmap {
+        MultidistanceMultigraphHashmap::default()
+    }
+
+    /// Builds a multigraph from a tuple edge list, keeping every edge between
+    /// a node pair.
+    #[must_use]
+    pub fn from_tuple_edge_list(
+        edges: &[(usize, usize, usize, usize, usize, f32)],
+    ) -> MultidistanceMultigraphHashmap {
+        let mut graph = MultidistanceMultigraphHashmap::new();
+        for (edge_from, edge_to, layer_start, layer_end, layer_weight_index, weight) in edges {
+            let edge_weight =
+                MultiDistance::from_tuple(*layer_start, *layer_end, *layer_weight_index, *weight);
+
+            graph.add_edge(NodeID(*edge_from), NodeID(*edge_to), edge_weight);
+        }
+        graph
+    }
+
+    /// All edges from `from` to `to`, in insertion order.
+    #[must_use]
+    pub fn parallel_edges(&self, from: NodeID, to: NodeID) -> &[MultiDistance] {
+        self.edges
+            .get(&from)
+            .and_then(|targets| targets.get(&to))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Every edge as `(from, to, weight)`.
+    pub fn edge_list(&self) -> impl Iterator<Item = (NodeID, NodeID, &MultiDistance)> {
+        self.edges.iter().flat_map(|(from, targets)| {
+            targets
+                .iter()
+                .flat_map(move |(to, weights)| weights.iter().map(move |w| (*from, *to, w)))
+        })
+    }
+}
+
+impl MultidistanceGraph for MultidistanceMultigraphHashmap {
+    fn nodes(&self) -> Vec<NodeID> {
+        self.edges.keys().copied().collect()
+    }
+
+    fn add_node(&mut self, node: NodeID) {
+        self.edges.entry(node).or_default();
+    }
+
+    fn add_edge(&mut self, from: NodeID, to: NodeID, weight: MultiDistance) {
+        let weights = self.edges.entry(from).or_default().entry(to).or_default();
+        if !weights.contains(&weight) {
+            weights.push(weight);
+            self.in_edges.entry(to).or_default().insert(from);
+        }
+        self.edges.entry(to).or_default(); // to ensure that sink nodes appear in node list
+    }
+
+    /// Removes all parallel edges from `from` to `to`.
+    fn remove_edge(&mut self, from: NodeID, to: NodeID) {
+        if let Some(neighbors) = self.edges.get_mut(&from) {
+            neighbors.remove(&to);
+        }
+        if let Some(sources) = self.in_edges.get_mut(&to) {
+            sources.remove(&from);
+        }
+    }
+
+    fn neighbor_edges(&self, node: &NodeID) -> Vec<(NodeID, MultiDistance)> {
+        self.edges
+            .get(node)
+            .map(|targets| {
+                targets
+                    .iter()
+                    .flat_map(|(target, weights)| weights.iter().map(|w| (*target, w.clone())))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn in_neighbor_edges(&self, node: &NodeID) -> Vec<(NodeID, MultiDistance)> {
+        self.in_edges
+            .get(node)
+            .map(|sources| {
+                sources
+                    .iter()
+                    .flat_map(|source| {
+                        self.parallel_edges(*source, *node)
+                            .iter()
+                            .map(|w| (*source, w.clone()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The first of the parallel edges from `from` to `to`.
+    fn edge_weight(&self, from: NodeID, to: NodeID) -> Option<&MultiDistance> {
+        self.parallel_edges(from, to).first()
+    }
+
+    fn remove_edge_with_weight(&mut self, from: NodeID, to: NodeID, weight: &MultiDistance) {
+        if let Some(neighbors) = self.edges.get_mut(&from) {
+            if let Some(weights) = neighbors.get_mut(&to) {
+                weights.retain(|w| w != weight);
+                if weights.is_empty() {
+                    neighbors.remove(&to);
+                    if let Some(sources) = self.in_edges.get_mut(&to) {
+                        sources.remove(&from);
+                    }
+                }
+            }
+        }
+    }
+}
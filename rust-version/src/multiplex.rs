@@ -0,0 +1,544 @@
+use crate::multidistance::{EdgeLayerID, MultiDistance, NodeID};
+use crate::multigraph::{MultidistanceGraph, MultidistanceGraphHashmap};
+use crate::node_registry::NodeRegistry;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+/// A node as it appears in one layer of a multiplex graph.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, PartialOrd, Ord)]
+pub struct StateNode {
+    pub node: NodeID,
+    pub layer: usize,
+}
+
+impl fmt::Display for StateNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.node, self.layer)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MultiplexError {
+    /// The `NodeID` has no `StateNode` in the registry.
+    UnknownNode(NodeID),
+    /// The edge layers do not match the layers of its endpoints.
+    LayerMismatch {
+        source: StateNode,
+        target: StateNode,
+        layer: EdgeLayerID,
+    },
+    /// The edge weight spans more than one `EdgeLayerID`.
+    NotSingleLayer(NodeID, NodeID),
+    /// Interlayer weights were set while the coupling depends on the node.
+    PerNodeCoupling,
+}
+
+impl fmt::Display for MultiplexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MultiplexError::UnknownNode(node) => write!(f, "{node} has no state node"),
+            MultiplexError::LayerMismatch {
+                source,
+                target,
+                layer,
+            } => write!(f, "edge {source} -> {target} has layers {layer:?}"),
+            MultiplexError::NotSingleLayer(source, target) => {
+                write!(f, "edge {source} -> {target} spans several layers")
+            }
+            MultiplexError::PerNodeCoupling => {
+                write!(f, "the coupling weights depend on the node, not the layers")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MultiplexError {}
+
+/// How the copies of a node in different layers are coupled.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum CouplingPolicy {
+    /// Layers are not coupled.
+    #[default]
+    None,
+    /// Every pair of copies is coupled with the same weight.
+    Uniform(f32),
+    /// Copies are coupled with a weight that depends on the directed
+    /// `(from_layer, to_layer)` pair; missing pairs get the `default` weight,
+    /// or are not coupled if it is `None`.
+    PerLayerPair {
+        weights: HashMap<(usize, usize), f32>,
+        default: Option<f32>,
+    },
+    /// The copies of each node are coupled with a weight that depends on the
+    /// node; missing nodes are not coupled.
+    PerNode(HashMap<NodeID, f32>),
+}
+
+impl CouplingPolicy {
+    /// Weight of the coupling edge from `node` in `from_layer` to `node` in
+    /// `to_layer`, or `None` if the two copies are not coupled.
+    #[must_use]
+    pub fn weight(&self, node: NodeID, from_layer: usize, to_layer: usize) -> Option<f32> {
+        match self {
+            CouplingPolicy::None => None,
+            CouplingPolicy::Uniform(weight) => Some(*weight),
+            CouplingPolicy::PerLayerPair { weights, default } => {
+                weights.get(&(from_layer, to_layer)).copied().or(*default)
+            }
+            CouplingPolicy::PerNode(weights) => weights.get(&node).copied(),
+        }
+    }
+
+    /// Coupling edges between the copies of every node in `state_nodes`.
+    fn coupling_edges(
+        &self,
+        state_nodes: &BTreeSet<StateNode>,
+    ) -> Vec<(StateNode, StateNode, f32)> {
+        let mut coupling = Vec::new();
+        let states: Vec<&StateNode> = state_nodes.iter().collect();
+        // the copies of a node are adjacent in (node, layer) order
+        for copies in states.chunk_by(|a, b| a.node == b.node) {
+            for from in copies {
+                for to in copies {
+                    if from.layer == to.layer {
+                        continue;
+                    }
+                    if let Some(weight) = self.weight(from.node, from.layer, to.layer) {
+                        coupling.push((**from, **to, weight));
+                    }
+                }
+            }
+        }
+        coupling
+    }
+}
+
+/// Builds a supra-graph edge list from one edge list per layer. The layer of
+/// an edge is the index of its list; nodes are identified across layers by
+/// their index, and copies of the same node are coupled according to
+/// `coupling`.
+///
+/// Returns the edges in the tuple format of
+/// `MultidistanceGraphHashmap::from_tuple_edge_list`, together with the
+/// registry that maps each `StateNode` to its supra-graph node.
+#[must_use]
+#[allow(clippy::type_complexity)]
+pub fn coupled_tuple_edge_list(
+    layers: &[Vec<(usize, usize, f32)>],
+    coupling: &CouplingPolicy,
+) -> (
+    Vec<(usize, usize, usize, usize, usize, f32)>,
+    NodeRegistry<StateNode>,
+) {
+    let state = |node: usize, layer: usize| StateNode {
+        node: NodeID(node),
+        layer,
+    };
+    let state_nodes: BTreeSet<StateNode> = layers
+        .iter()
+        .enumerate()
+        .flat_map(|(layer, edges)| {
+            edges
+                .iter()
+                .flat_map(move |(from, to, _)| [state(*from, layer), state(*to, layer)])
+        })
+        .collect();
+
+    let mut registry = NodeRegistry::new();
+    for s in &state_nodes {
+        registry.get_or_insert(*s);
+    }
+    let index = |s: StateNode| {
+        registry
+            .id(&s)
+            .expect("every state node has been registered")
+            .0
+    };
+
+    let mut edges = Vec::new();
+    for (layer, layer_edges) in layers.iter().enumerate() {
+        for (from, to, weight) in layer_edges {
+            edges.push((
+                index(state(*from, layer)),
+                index(state(*to, layer)),
+                layer,
+                layer,
+                0,
+                *weight,
+            ));
+        }
+    }
+    for (from, to, weight) in coupling.coupling_edges(&state_nodes) {
+        edges.push((index(from), index(to), from.layer, to.layer, 0, weight));
+    }
+    (edges, registry)
+}
+
+impl MultidistanceGraphHashmap {
+    /// Builds the supra-graph of [`coupled_tuple_edge_list`] directly.
+    #[must_use]
+    pub fn from_layer_edge_lists(
+        layers: &[Vec<(usize, usize, f32)>],
+        coupling: &CouplingPolicy,
+    ) -> (MultidistanceGraphHashmap, NodeRegistry<StateNode>) {
+        let (edges, registry) = coupled_tuple_edge_list(layers, coupling);
+        (
+            MultidistanceGraphHashmap::from_tuple_edge_list(&edges),
+            registry,
+        )
+    }
+}
+
+/// A multiplex graph in which every node lives in one or more layers.
+///
+/// Edges connect state nodes, i.e. (node, layer) pairs. Edges inside a layer
+/// and explicit edges between layers are stored as given; coupling edges
+/// between the copies of a node in two layers are generated when the graph is
+/// converted, according to the graph's `CouplingPolicy`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MultiplexGraph {
+    state_nodes: BTreeSet<StateNode>,
+    edges: HashMap<StateNode, HashMap<StateNode, f32>>,
+    coupling: CouplingPolicy,
+}
+
+impl MultiplexGraph {
+    #[must_use]
+    pub fn new() -> MultiplexGraph {
+        MultiplexGraph::default()
+    }
+
+    pub fn add_node(&mut self, node: NodeID, layer: usize) {
+        self.state_nodes.insert(StateNode { node, layer });
+    }
+
+    pub fn add_intralayer_edge(&mut self, layer: usize, from: NodeID, to: NodeID, weight: f32) {
+        self.add_edge(
+            StateNode { node: from, layer },
+            StateNode { node: to, layer },
+            weight,
+        );
+    }
+
+    pub fn add_edge(&mut self, from: StateNode, to: StateNode, weight: f32) {
+        self.state_nodes.insert(from);
+        self.state_nodes.insert(to);
+        self.edges.entry(from).or_default().insert(to, weight);
+    }
+
+    pub fn set_coupling(&mut self, coupling: CouplingPolicy) {
+        self.coupling = coupling;
+    }
+
+    /// Sets the weight of the coupling edges from `from_layer` to `to_layer`.
+    /// Coupling is directed, so set both directions for symmetric coupling.
+    ///
+    /// A `Uniform` coupling keeps its weight as the default of the other layer
+    /// pairs.
+    ///
+    /// # Errors
+    /// Returns `MultiplexError::PerNodeCoupling` if the coupling depends on the
+    /// node.
+    pub fn set_interlayer_weight(
+        &mut self,
+        from_layer: usize,
+        to_layer: usize,
+        weight: f32,
+    ) -> Result<(), MultiplexError> {
+        self.update_layer_pair_coupling(|weights, _| {
+            weights.insert((from_layer, to_layer), weight);
+        })
+    }
+
+    /// Sets the coupling weight of every layer pair without an explicit
+    /// weight. With `None`, such layer pairs are not coupled.
+    ///
+    /// # Errors
+    /// Returns `MultiplexError::PerNodeCoupling` if the coupling depends on the
+    /// node.
+    pub fn set_default_interlayer_weight(
+        &mut self,
+        weight: Option<f32>,
+    ) -> Result<(), MultiplexError> {
+        self.update_layer_pair_coupling(|_, default| *default = weight)
+    }
+
+    /// The weight of the coupling edges from `from_layer` to `to_layer`, or
+    /// `None` if the layers are not coupled or the weight depends on the node.
+    #[must_use]
+    pub fn interlayer_weight(&self, from_layer: usize, to_layer: usize) -> Option<f32> {
+        match &self.coupling {
+            CouplingPolicy::None | CouplingPolicy::PerNode(_) => None,
+            CouplingPolicy::Uniform(weight) => Some(*weight),
+            CouplingPolicy::PerLayerPair { weights, default } => {
+                weights.get(&(from_layer, to_layer)).copied().or(*default)
+            }
+        }
+    }
+
+    /// Applies `update` to the explicit weights and the default of the
+    /// coupling, seen as a `PerLayerPair` one with the same weights.
+    fn update_layer_pair_coupling(
+        &mut self,
+        update: impl FnOnce(&mut HashMap<(usize, usize), f32>, &mut Option<f32>),
+    ) -> Result<(), MultiplexError> {
+        let (mut weights, mut default) = match &self.coupling {
+            CouplingPolicy::PerNode(_) => return Err(MultiplexError::PerNodeCoupling),
+            CouplingPolicy::None => (HashMap::new(), None),
+            CouplingPolicy::Uniform(weight) => (HashMap::new(), Some(*weight)),
+            CouplingPolicy::PerLayerPair { weights, default } => (weights.clone(), *default),
+        };
+        update(&mut weights, &mut default);
+        self.coupling = CouplingPolicy::PerLayerPair { weights, default };
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn coupling(&self) -> &CouplingPolicy {
+        &self.coupling
+    }
+
+    /// State nodes in ascending (node, layer) order.
+    pub fn state_nodes(&self) -> impl Iterator<Item = &StateNode> {
+        self.state_nodes.iter()
+    }
+
+    /// Layers in which `node` has a state node.
+    #[must_use]
+    pub fn layers_of(&self, node: NodeID) -> Vec<usize> {
+        self.state_nodes
+            .range(
+                StateNode { node, layer: 0 }..=StateNode {
+                    node,
+                    layer: usize::MAX,
+                },
+            )
+            .map(|s| s.layer)
+            .collect()
+    }
+
+    /// Explicitly added edges, both within and between layers.
+    #[must_use]
+    pub fn edges(&self) -> Vec<(StateNode, StateNode, f32)> {
+        self.edges
+            .iter()
+            .flat_map(|(from, targets)| targets.iter().map(|(to, w)| (*from, *to, *w)))
+            .collect()
+    }
+
+    /// Edges between the copies of each node in every coupled pair of layers.
+    /// Explicitly added edges take precedence over generated ones.
+    #[must_use]
+    pub fn coupling_edges(&self) -> Vec<(StateNode, StateNode, f32)> {
+        self.coupling
+            .coupling_edges(&self.state_nodes)
+            .into_iter()
+            .filter(|(from, to, _)| !self.edges.get(from).is_some_and(|t| t.contains_key(to)))
+            .collect()
+    }
+
+    /// Converts to a supra-graph whose nodes are the state nodes. The returned
+    /// registry maps each `StateNode` to its `NodeID` in the new graph; IDs
+    /// follow the ascending (node, layer) order.
+    #[must_use]
+    pub fn to_multidistance_graph(&self) -> (MultidistanceGraphHashmap, NodeRegistry<StateNode>) {
+        let mut registry = NodeRegistry::new();
+        for state in &self.state_nodes {
+            registry.get_or_insert(*state);
+        }
+
+        let mut graph = MultidistanceGraphHashmap::new();
+        for state in &self.state_nodes {
+            // isolated state nodes still belong to the graph
+            graph.add_node(registry.get_or_insert(*state));
+        }
+        for (from, to, weight) in self.edges().into_iter().chain(self.coupling_edges()) {
+            graph.add_edge(
+                registry.get_or_insert(from),
+                registry.get_or_insert(to),
+                MultiDistance::from_tuple(from.layer, to.layer, 0, weight),
+            );
+        }
+        (graph, registry)
+    }
+
+    /// Rebuilds a multiplex graph from a supra-graph and the registry that maps
+    /// its nodes to state nodes. Every edge is kept as an explicit edge, so the
+    /// result has no coupling policy.
+    ///
+    /// # Errors
+    /// Returns a `MultiplexError` if a node is missing from `registry`, or if
+    /// an edge weight does not consist of a single layer matching the layers
+    /// of its endpoints.
+    pub fn from_multidistance_graph(
+        graph: &impl MultidistanceGraph,
+        registry: &NodeRegistry<StateNode>,
+    ) -> Result<MultiplexGraph, MultiplexError> {
+        let state_of = |id: NodeID| {
+            registry
+                .label(id)
+                .copied()
+                .ok_or(MultiplexError::UnknownNode(id))
+        };
+
+        let mut multiplex = MultiplexGraph::new();
+        for id in graph.nodes() {
+            let from = state_of(id)?;
+            multiplex.state_nodes.insert(from);
+            for (target, distance) in graph.neighbor_edges(&id) {
+                let to = state_of(target)?;
+                let mut layers = distance.total.iter();
+                let weight = match (layers.next(), layers.next()) {
+                    (None, _) => 0.0,
+                    (Some((layer, weight)), None) => {
+                        if layer.layer_start != from.layer || layer.layer_end != to.layer {
+                            return Err(MultiplexError::LayerMismatch {
+                                source: from,
+                                target: to,
+                                layer: *layer,
+                            });
+                        }
+                        *weight
+                    }
+                    (Some(_), Some(_)) => return Err(MultiplexError::NotSingleLayer(id, target)),
+                };
+                multiplex.add_edge(from, to, weight);
+            }
+        }
+        Ok(multiplex)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coupling_and_round_trip() {
+        let mut multiplex = MultiplexGraph::new();
+        multiplex.add_intralayer_edge(0, NodeID(0), NodeID(1), 1.0);
+        multiplex.add_intralayer_edge(1, NodeID(1), NodeID(2), 2.0);
+        multiplex.add_node(NodeID(3), 1);
+        multiplex.set_default_interlayer_weight(Some(0.5)).unwrap();
+        multiplex.set_interlayer_weight(1, 0, 3.0).unwrap();
+        assert_eq!(multiplex.interlayer_weight(0, 1), Some(0.5));
+        assert_eq!(
+            multiplex.coupling(),
+            &CouplingPolicy::PerLayerPair {
+                weights: HashMap::from([((1, 0), 3.0)]),
+                default: Some(0.5),
+            }
+        );
+
+        assert_eq!(multiplex.layers_of(NodeID(1)), vec![0, 1]);
+        assert_eq!(multiplex.layers_of(NodeID(3)), vec![1]);
+
+        let mut coupling = multiplex.coupling_edges();
+        coupling.sort_by_key(|edge| edge.0);
+        let s10 = StateNode {
+            node: NodeID(1),
+            layer: 0,
+        };
+        let s11 = StateNode {
+            node: NodeID(1),
+            layer: 1,
+        };
+        assert_eq!(coupling, vec![(s10, s11, 0.5), (s11, s10, 3.0)]);
+
+        let (graph, registry) = multiplex.to_multidistance_graph();
+        assert_eq!(registry.len(), 5);
+        assert_eq!(graph.nodes().len(), 5);
+        let id10 = registry.id(&s10).unwrap();
+        let id11 = registry.id(&s11).unwrap();
+        assert_eq!(
+            graph.edge_weight(id10, id11),
+            Some(&MultiDistance::from_tuple(0, 1, 0, 0.5))
+        );
+
+        let rebuilt = MultiplexGraph::from_multidistance_graph(&graph, &registry).unwrap();
+        let (graph_again, _) = rebuilt.to_multidistance_graph();
+        assert!(graph_again == graph);
+
+        let mut bad = graph.clone();
+        bad.add_edge(id10, id11, MultiDistance::from_tuple(0, 0, 0, 1.0));
+        assert!(matches!(
+            MultiplexGraph::from_multidistance_graph(&bad, &registry),
+            Err(MultiplexError::LayerMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_layer_edge_lists_with_coupling() {
+        let layers = vec![vec![(0, 1, 1.0), (1, 2, 1.0)], vec![(0, 2, 4.0)]];
+
+        let (edges, registry) = coupled_tuple_edge_list(&layers, &CouplingPolicy::None);
+        assert_eq!(registry.len(), 5);
+        assert_eq!(edges.len(), 3);
+
+        let (mut edges, registry) = coupled_tuple_edge_list(&layers, &CouplingPolicy::Uniform(0.0));
+        edges.sort_by_key(|edge| (edge.0, edge.1));
+        let id = |node, layer| {
+            registry
+                .id(&StateNode {
+                    node: NodeID(node),
+                    layer,
+                })
+                .unwrap()
+                .0
+        };
+        assert_eq!(
+            edges,
+            vec![
+                (id(0, 0), id(0, 1), 0, 1, 0, 0.0),
+                (id(0, 0), id(1, 0), 0, 0, 0, 1.0),
+                (id(0, 1), id(0, 0), 1, 0, 0, 0.0),
+                (id(0, 1), id(2, 1), 1, 1, 0, 4.0),
+                (id(1, 0), id(2, 0), 0, 0, 0, 1.0),
+                (id(2, 0), id(2, 1), 0, 1, 0, 0.0),
+                (id(2, 1), id(2, 0), 1, 0, 0, 0.0),
+            ]
+        );
+
+        let per_node = CouplingPolicy::PerNode(HashMap::from([(NodeID(2), 1.5)]));
+        let (graph, registry) =
+            MultidistanceGraphHashmap::from_layer_edge_lists(&layers, &per_node);
+        let s20 = registry
+            .id(&StateNode {
+                node: NodeID(2),
+                layer: 0,
+            })
+            .unwrap();
+        let s21 = registry
+            .id(&StateNode {
+                node: NodeID(2),
+                layer: 1,
+            })
+            .unwrap();
+        assert_eq!(
+            graph.edge_weight(s21, s20),
+            Some(&MultiDistance::from_tuple(1, 0, 0, 1.5))
+        );
+        assert_eq!(graph.neighbor_edges(&s20).len(), 1);
+    }
+
+    #[test]
+    fn test_interlayer_weights_keep_other_couplings() {
+        let mut multiplex = MultiplexGraph::new();
+        multiplex.set_coupling(CouplingPolicy::Uniform(2.0));
+        multiplex.set_interlayer_weight(0, 1, 1.0).unwrap();
+        assert_eq!(multiplex.interlayer_weight(0, 1), Some(1.0));
+        assert_eq!(multiplex.interlayer_weight(1, 0), Some(2.0));
+
+        let per_node = CouplingPolicy::PerNode(HashMap::from([(NodeID(0), 1.0)]));
+        multiplex.set_coupling(per_node.clone());
+        assert_eq!(
+            multiplex.set_interlayer_weight(0, 1, 1.0),
+            Err(MultiplexError::PerNodeCoupling)
+        );
+        assert_eq!(
+            multiplex.set_default_interlayer_weight(None),
+            Err(MultiplexError::PerNodeCoupling)
+        );
+        assert_eq!(multiplex.coupling(), &per_node);
+    }
+}
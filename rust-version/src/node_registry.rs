@@ -0,0 +1,171 @@
+use crate::multidistance::NodeID;
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// A tuple edge whose endpoints are arbitrary labels instead of `usize`
+/// indices. The remaining fields are the same as in
+/// `MultidistanceGraphHashmap::from_tuple_edge_list`.
+pub type LabeledTupleEdge<L> = (L, L, usize, usize, usize, f32);
+
+/// Interns node labels (institution codes, concept names, ...) as `NodeID`s
+/// and maps them back when results are reported.
+///
+/// IDs are handed out consecutively from zero in order of first appearance.
+#[derive(Clone, Debug)]
+pub struct NodeRegistry<L = String> {
+    ids: HashMap<L, NodeID>,
+    labels: Vec<L>,
+}
+
+impl<L> Default for NodeRegistry<L> {
+    fn default() -> Self {
+        NodeRegistry {
+            ids: HashMap::new(),
+            labels: Vec::new(),
+        }
+    }
+}
+
+impl<L> NodeRegistry<L>
+where
+    L: Hash + Eq + Clone,
+{
+    #[must_use]
+    pub fn new() -> NodeRegistry<L> {
+        NodeRegistry::default()
+    }
+
+    /// Returns the `NodeID` of `label`, registering it first if needed.
+    pub fn get_or_insert(&mut self, label: L) -> NodeID {
+        if let Some(id) = self.ids.get(&label) {
+            return *id;
+        }
+        let id = NodeID(self.labels.len());
+        self.labels.push(label.clone());
+        self.ids.insert(label, id);
+        id
+    }
+
+    #[must_use]
+    pub fn id<Q>(&self, label: &Q) -> Option<NodeID>
+    where
+        L: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.ids.get(label).copied()
+    }
+
+    #[must_use]
+    pub fn label(&self, id: NodeID) -> Option<&L> {
+        self.labels.get(id.0)
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// Labels in `NodeID` order.
+    pub fn labels(&self) -> impl Iterator<Item = &L> {
+        self.labels.iter()
+    }
+
+    /// Converts a labeled edge list into the tuple format used by the rest of
+    /// the crate, registering any new labels along the way.
+    pub fn intern_edge_list(
+        &mut self,
+        edges: &[LabeledTupleEdge<L>],
+    ) -> Vec<(usize, usize, usize, usize, usize, f32)> {
+        edges
+            .iter()
+            .map(
+                |(source, target, layer_start, layer_end, layer_weight_index, weight)| {
+                    let NodeID(source) = self.get_or_insert(source.clone());
+                    let NodeID(target) = self.get_or_insert(target.clone());
+                    (
+                        source,
+                        target,
+                        *layer_start,
+                        *layer_end,
+                        *layer_weight_index,
+                        *weight,
+                    )
+                },
+            )
+            .collect()
+    }
+
+    /// Replaces the `NodeID` keys of a closure, backbone or adjacency map by
+    /// their labels.
+    ///
+    /// # Panics
+    /// Panics if the map contains a `NodeID` that was not issued by this
+    /// registry.
+    #[must_use]
+    pub fn label_nested_map<V: Clone>(
+        &self,
+        map: &HashMap<NodeID, HashMap<NodeID, V>>,
+    ) -> HashMap<L, HashMap<L, V>> {
+        let label = |id: &NodeID| {
+            self.label(*id)
+                .unwrap_or_else(|| panic!("{id} was not issued by this registry"))
+                .clone()
+        };
+        map.iter()
+            .map(|(source, targets)| {
+                (
+                    label(source),
+                    targets
+                        .iter()
+                        .map(|(target, value)| (label(target), value.clone()))
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{multilayer_backbone, MultiDistance};
+
+    #[test]
+    fn test_labels_round_trip() {
+        let mut registry = NodeRegistry::new();
+        let edges = registry.intern_edge_list(&[
+            ("M25486".to_string(), "M10231".to_string(), 0, 0, 0, 1.0),
+            ("M10231".to_string(), "M77777".to_string(), 0, 0, 0, 1.0),
+            ("M25486".to_string(), "M77777".to_string(), 0, 0, 0, 3.0),
+        ]);
+
+        assert_eq!(registry.len(), 3);
+        assert_eq!(registry.id("M10231"), Some(NodeID(1)));
+        assert_eq!(
+            registry.label(NodeID(2)).map(String::as_str),
+            Some("M77777")
+        );
+        assert_eq!(registry.id("missing"), None);
+        assert_eq!(edges[2], (0, 2, 0, 0, 0, 3.0));
+
+        let backbone = registry.label_nested_map(&multilayer_backbone(&edges));
+        let one = MultiDistance::from_tuple(0, 0, 0, 1.0);
+        let expected = HashMap::from([
+            (
+                "M25486".to_string(),
+                HashMap::from([("M10231".to_string(), vec![one.clone()])]),
+            ),
+            (
+                "M10231".to_string(),
+                HashMap::from([("M77777".to_string(), vec![one])]),
+            ),
+        ]);
+        assert_eq!(backbone, expected);
+    }
+}
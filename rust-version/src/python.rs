@@ -0,0 +1,1551 @@
+//! Python bindings, built with the `python` feature.
+
+// the impls generated by pyo3 0.20's `#[pymethods]` trigger this lint
+#![allow(non_local_definitions)]
+
+use crate::*;
+use pyo3::basic::CompareOp;
+use pyo3::exceptions::{PyIndexError, PyKeyError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList, PyTuple};
+use std::collections::HashMap;
+
+impl IntoPy<PyObject> for NodeID {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        let NodeID(node_id_val) = self;
+        node_id_val.into_py(py)
+    }
+}
+
+impl<'source> FromPyObject<'source> for NodeID {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        Ok(NodeID(ob.extract()?))
+    }
+}
+
+impl IntoPy<PyObject> for EdgeLayerID {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        ((self.layer_start, self.layer_end), self.layer_weight_index).into_py(py)
+    }
+}
+
+impl<'source> FromPyObject<'source> for EdgeLayerID {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        let ((layer_start, layer_end), layer_weight_index) = ob.extract()?;
+        Ok(EdgeLayerID {
+            layer_start,
+            layer_end,
+            layer_weight_index,
+        })
+    }
+}
+
+impl IntoPy<PyObject> for MultiDistance {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        self.total.into_py(py)
+    }
+}
+
+/// Accepts a `MultiDistance` object or a `{((layer_start, layer_end),
+/// layer_weight_index): weight}` dict.
+impl<'source> FromPyObject<'source> for MultiDistance {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        if let Ok(distance) = ob.extract::<PyRef<PyMultiDistance>>() {
+            return Ok(distance.0.clone());
+        }
+        let weights: Vec<(EdgeLayerID, f32)> = ob
+            .extract::<HashMap<EdgeLayerID, f32>>()?
+            .into_iter()
+            .collect();
+        Ok(MultiDistance::from_layer_weights(&weights))
+    }
+}
+
+impl IntoPy<PyObject> for DuplicateSummary {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        let dict = HashMap::from([
+            ("n_input_edges", self.n_input_edges.into_py(py)),
+            ("n_output_edges", self.n_output_edges.into_py(py)),
+            ("duplicates", self.duplicates.into_py(py)),
+        ]);
+        dict.into_py(py)
+    }
+}
+
+impl IntoPy<PyObject> for BackboneComparison {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        HashMap::from([
+            ("multilayer_only", self.multilayer_only),
+            ("layer_only", self.layer_only),
+            ("both", self.both),
+        ])
+        .into_py(py)
+    }
+}
+
+/// A `MultiDistance`. Comparisons follow the partial order of distances:
+/// `a < b` if `a` is no longer than `b` in every layer and shorter in one, and
+/// two distances that are shorter in different layers are incomparable.
+#[pyclass(name = "MultiDistance")]
+#[derive(Clone)]
+struct PyMultiDistance(MultiDistance);
+
+#[pymethods]
+impl PyMultiDistance {
+    #[new]
+    #[pyo3(signature = (weights=None))]
+    fn new(weights: Option<MultiDistance>) -> Self {
+        PyMultiDistance(weights.unwrap_or_default())
+    }
+
+    /// The distance of a single edge.
+    #[staticmethod]
+    fn from_tuple(
+        layer_start: usize,
+        layer_end: usize,
+        layer_weight_index: usize,
+        weight: f32,
+    ) -> Self {
+        PyMultiDistance(MultiDistance::from_tuple(
+            layer_start,
+            layer_end,
+            layer_weight_index,
+            weight,
+        ))
+    }
+
+    /// The weights as a `{((layer_start, layer_end), layer_weight_index): weight}` dict.
+    #[getter]
+    fn weights(&self) -> MultiDistance {
+        self.0.clone()
+    }
+
+    /// Whether `self` is strictly shorter than `other`.
+    fn dominates(&self, other: MultiDistance) -> bool {
+        self.0 < other
+    }
+
+    /// Whether one of `self` and `other` is no longer than the other.
+    fn is_comparable(&self, other: MultiDistance) -> bool {
+        self.0.partial_cmp(&other).is_some()
+    }
+
+    fn __add__(&self, other: MultiDistance) -> Self {
+        PyMultiDistance(self.0.clone() + other)
+    }
+
+    fn __richcmp__(&self, other: &PyAny, op: CompareOp, py: Python<'_>) -> PyObject {
+        let Ok(other) = other.extract::<MultiDistance>() else {
+            return py.NotImplemented();
+        };
+        let result = match op {
+            CompareOp::Lt => self.0 < other,
+            CompareOp::Le => self.0 <= other,
+            CompareOp::Eq => self.0 == other,
+            CompareOp::Ne => self.0 != other,
+            CompareOp::Gt => self.0 > other,
+            CompareOp::Ge => self.0 >= other,
+        };
+        result.into_py(py)
+    }
+
+    fn __repr__(&self) -> String {
+        let mut entries: Vec<_> = self.0.total.iter().collect();
+        entries.sort_unstable_by_key(|(layer, _)| {
+            (layer.layer_start, layer.layer_end, layer.layer_weight_index)
+        });
+        let entries: Vec<String> = entries
+            .into_iter()
+            .map(|(layer, weight)| {
+                format!(
+                    "(({}, {}), {}): {weight:?}",
+                    layer.layer_start, layer.layer_end, layer.layer_weight_index
+                )
+            })
+            .collect();
+        format!("MultiDistance({{{}}})", entries.join(", "))
+    }
+}
+
+/// A `MultidistanceGraphHashmap` that is built once and can then be queried
+/// and reduced to its backbone repeatedly.
+#[pyclass(name = "MultidistanceGraph")]
+#[derive(Clone, Default)]
+struct PyMultidistanceGraph(MultidistanceGraphHashmap);
+
+#[pymethods]
+impl PyMultidistanceGraph {
+    #[new]
+    #[pyo3(signature = (edges=None))]
+    #[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
+    #[allow(clippy::type_complexity)]
+    fn new(edges: Option<Vec<(usize, usize, usize, usize, usize, f32)>>) -> Self {
+        edges.map_or_else(PyMultidistanceGraph::default, |edges| {
+            PyMultidistanceGraph(MultidistanceGraphHashmap::from_tuple_edge_list(&edges))
+        })
+    }
+
+    /// Adds an edge, replacing any edge from `source` to `target`.
+    fn add_edge(&mut self, source: NodeID, target: NodeID, weight: MultiDistance) {
+        self.0.add_edge(source, target, weight);
+    }
+
+    fn remove_edge(&mut self, source: NodeID, target: NodeID) {
+        self.0.remove_edge(source, target);
+    }
+
+    fn edge_weight(&self, source: NodeID, target: NodeID) -> Option<PyMultiDistance> {
+        self.0
+            .edge_weight(source, target)
+            .cloned()
+            .map(PyMultiDistance)
+    }
+
+    fn nodes(&self) -> Vec<NodeID> {
+        let mut nodes = self.0.nodes();
+        nodes.sort_unstable();
+        nodes
+    }
+
+    /// The out-neighbors of `node` with the weights of the edges to them.
+    fn neighbors(&self, node: NodeID) -> Vec<(NodeID, PyMultiDistance)> {
+        let mut neighbors = self.0.neighbor_edges(&node);
+        neighbors.sort_unstable_by_key(|(target, _)| *target);
+        neighbors
+            .into_iter()
+            .map(|(target, weight)| (target, PyMultiDistance(weight)))
+            .collect()
+    }
+
+    /// The in-neighbors of `node` with the weights of the edges from them.
+    fn in_neighbors(&self, node: NodeID) -> Vec<(NodeID, PyMultiDistance)> {
+        let mut neighbors = self.0.in_neighbor_edges(&node);
+        neighbors.sort_unstable_by_key(|(source, _)| *source);
+        neighbors
+            .into_iter()
+            .map(|(source, weight)| (source, PyMultiDistance(weight)))
+            .collect()
+    }
+
+    /// Every edge as `(source, target, weight)`.
+    fn edges(&self) -> Vec<(NodeID, NodeID, PyMultiDistance)> {
+        self.nodes()
+            .into_iter()
+            .flat_map(|source| {
+                self.neighbors(source)
+                    .into_iter()
+                    .map(move |(target, weight)| (source, target, weight))
+            })
+            .collect()
+    }
+
+    fn n_edges(&self) -> usize {
+        self.0.edges.values().map(HashMap::len).sum()
+    }
+
+    /// The number of nodes, like `len` of a networkx graph.
+    fn __len__(&self) -> usize {
+        self.0.edges.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "MultidistanceGraph(n_nodes={}, n_edges={})",
+            self.__len__(),
+            self.n_edges()
+        )
+    }
+
+    fn copy(&self) -> Self {
+        self.clone()
+    }
+
+    /// Same as `distance_closure_py`.
+    fn closure(&self, py: Python<'_>) -> MultidistanceClosure {
+        py.allow_threads(|| multidistance_closure(&self.0))
+    }
+
+    /// The Pareto set of distances from `source` to every node it reaches,
+    /// using paths of at most `n_steps` edges if given.
+    #[pyo3(signature = (source, n_steps=None))]
+    fn shortest_distances(
+        &self,
+        py: Python<'_>,
+        source: NodeID,
+        n_steps: Option<usize>,
+    ) -> HashMap<NodeID, Vec<PyMultiDistance>> {
+        py.allow_threads(|| parteto_shortest_distance_from_source(source, &self.0, n_steps, None))
+            .into_iter()
+            .map(|(target, distances)| {
+                (target, distances.into_iter().map(PyMultiDistance).collect())
+            })
+            .collect()
+    }
+
+    /// The Pareto set of distances from `source` to `target`, without
+    /// searching the rest of the graph (see `pareto_distances_between`).
+    fn pareto_distances(
+        &self,
+        py: Python<'_>,
+        source: NodeID,
+        target: NodeID,
+    ) -> Vec<PyMultiDistance> {
+        py.allow_threads(|| pareto_distances_between(&self.0, source, target))
+            .into_iter()
+            .map(PyMultiDistance)
+            .collect()
+    }
+
+    /// Whether the edge from `source` to `target` is metric, considering
+    /// paths of at most `n_steps` edges if given.
+    /// Raises `KeyError` if there is no such edge, and `ValueError` if no path
+    /// of at most `n_steps` edges reaches `target`, as with `n_steps=0`.
+    #[pyo3(signature = (source, target, n_steps=None))]
+    fn is_metric(
+        &self,
+        py: Python<'_>,
+        source: NodeID,
+        target: NodeID,
+        n_steps: Option<usize>,
+    ) -> PyResult<bool> {
+        if self.0.edge_weight(source, target).is_none() {
+            return Err(PyKeyError::new_err(format!(
+                "no edge from {} to {}",
+                source.0, target.0
+            )));
+        }
+        py.allow_threads(|| is_metric_in_n_steps(&self.0, source, target, n_steps))
+            .map_err(|_| {
+                PyValueError::new_err(format!(
+                    "{} is not reachable from {} in {} steps",
+                    target.0,
+                    source.0,
+                    n_steps.unwrap_or_default()
+                ))
+            })
+    }
+
+    /// Returns the backbone as a new graph. `algorithm` is one of `simas`,
+    /// `costa`, `costa-parallel` or `naive`.
+    #[pyo3(signature = (algorithm="costa-parallel"))]
+    fn backbone(&self, py: Python<'_>, algorithm: &str) -> PyResult<Self> {
+        let algorithm: fn(&mut MultidistanceGraphHashmap) = match algorithm {
+            "simas" => fast_backbone_simas,
+            "costa" => fast_backbone_costa,
+            "costa-parallel" => fast_backbone_costa_parallel,
+            "naive" => |graph| structural_backbone(graph, None),
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "unknown backbone algorithm {algorithm:?}"
+                )))
+            }
+        };
+        let mut graph = self.0.clone();
+        py.allow_threads(|| algorithm(&mut graph));
+        Ok(PyMultidistanceGraph(graph))
+    }
+}
+
+/// Every edge of an edge list with its classification, along with the
+/// closure it was computed from.
+#[pyclass(name = "BackboneResult")]
+struct PyBackboneResult {
+    edges: Vec<(usize, usize, usize, usize, usize, f32)>,
+    metric: Vec<bool>,
+    closure: MultidistanceClosure,
+}
+
+impl PyBackboneResult {
+    fn new(edges: Vec<(usize, usize, usize, usize, usize, f32)>) -> Self {
+        let closure = multidistance_closure(&MultidistanceMultigraphHashmap::from_tuple_edge_list(
+            &edges,
+        ));
+        let metric = edges
+            .iter()
+            .map(
+                |&(source, target, layer_start, layer_end, layer_weight_index, weight)| {
+                    let weight = MultiDistance::from_tuple(
+                        layer_start,
+                        layer_end,
+                        layer_weight_index,
+                        weight,
+                    );
+                    closure
+                        .get(&NodeID(source))
+                        .and_then(|targets| targets.get(&NodeID(target)))
+                        .is_some_and(|distances| distances.contains(&weight))
+                },
+            )
+            .collect();
+        PyBackboneResult {
+            edges,
+            metric,
+            closure,
+        }
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn edges_where(&self, metric: bool) -> Vec<(usize, usize, usize, usize, usize, f32)> {
+        self.edges
+            .iter()
+            .zip(&self.metric)
+            .filter(|(_, m)| **m == metric)
+            .map(|(edge, _)| *edge)
+            .collect()
+    }
+}
+
+/// The label of `index`, or the index itself without labels.
+fn label(py: Python<'_>, labels: Option<&[PyObject]>, index: usize) -> PyResult<PyObject> {
+    match labels {
+        Some(labels) => labels
+            .get(index)
+            .map(|label| label.clone_ref(py))
+            .ok_or_else(|| PyIndexError::new_err(format!("no label for index {index}"))),
+        None => Ok(index.into_py(py)),
+    }
+}
+
+#[pymethods]
+impl PyBackboneResult {
+    /// The metric edges, as tuple edges.
+    #[allow(clippy::type_complexity)]
+    fn edges(&self) -> Vec<(usize, usize, usize, usize, usize, f32)> {
+        self.edges_where(true)
+    }
+
+    /// The semi-metric edges, which are not part of the backbone.
+    #[allow(clippy::type_complexity)]
+    fn removed_edges(&self) -> Vec<(usize, usize, usize, usize, usize, f32)> {
+        self.edges_where(false)
+    }
+
+    /// Whether each input edge is metric, in input order.
+    fn metric_mask(&self) -> Vec<bool> {
+        self.metric.clone()
+    }
+
+    /// The Pareto set of distances from `source` to `target`.
+    fn pareto_distances(&self, source: NodeID, target: NodeID) -> Vec<PyMultiDistance> {
+        self.closure
+            .get(&source)
+            .and_then(|targets| targets.get(&target))
+            .map_or_else(Vec::new, |distances| {
+                distances.iter().cloned().map(PyMultiDistance).collect()
+            })
+    }
+
+    /// Same as `distance_closure_py`.
+    fn closure(&self) -> MultidistanceClosure {
+        self.closure.clone()
+    }
+
+    /// The backbone as a `networkx.MultiDiGraph` in the form read by
+    /// `backbone_networkx_py`: every node of the input, and every metric edge
+    /// with the attributes `weight` and `layer`. `nodes` and `layers` give the
+    /// label of every node and layer index.
+    #[pyo3(signature = (nodes=None, layers=None))]
+    #[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
+    fn to_networkx<'py>(
+        &self,
+        py: Python<'py>,
+        nodes: Option<Vec<PyObject>>,
+        layers: Option<Vec<PyObject>>,
+    ) -> PyResult<&'py PyAny> {
+        let (nodes, layers) = (nodes.as_deref(), layers.as_deref());
+        let graph = py.import("networkx")?.getattr("MultiDiGraph")?.call0()?;
+        let mut node_ids: Vec<usize> = self.edges.iter().flat_map(|e| [e.0, e.1]).collect();
+        node_ids.sort_unstable();
+        node_ids.dedup();
+        for node in node_ids {
+            graph.call_method1("add_node", (label(py, nodes, node)?,))?;
+        }
+
+        for (source, target, layer_start, layer_end, _, weight) in self.edges_where(true) {
+            let data = PyDict::new(py);
+            data.set_item("weight", weight)?;
+            if layer_start == layer_end {
+                data.set_item("layer", label(py, layers, layer_start)?)?;
+            } else {
+                let layer = (
+                    label(py, layers, layer_start)?,
+                    label(py, layers, layer_end)?,
+                );
+                data.set_item("layer", layer)?;
+            }
+            graph.call_method(
+                "add_edge",
+                (label(py, nodes, source)?, label(py, nodes, target)?),
+                Some(data),
+            )?;
+        }
+        Ok(graph)
+    }
+
+    /// Every input edge as a row of a `pandas.DataFrame`, with the columns
+    /// `source`, `target`, `layer_start`, `layer_end`, `layer_weight_index`,
+    /// `weight` and `metric`. `nodes` and `layers` give the label of every
+    /// node and layer index.
+    #[pyo3(signature = (nodes=None, layers=None))]
+    #[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
+    fn to_pandas<'py>(
+        &self,
+        py: Python<'py>,
+        nodes: Option<Vec<PyObject>>,
+        layers: Option<Vec<PyObject>>,
+    ) -> PyResult<&'py PyAny> {
+        let (nodes, layers) = (nodes.as_deref(), layers.as_deref());
+        let column = |labels: Option<&[PyObject]>, field: fn(&_) -> usize| {
+            self.edges
+                .iter()
+                .map(|edge| label(py, labels, field(edge)))
+                .collect::<PyResult<Vec<_>>>()
+        };
+        let columns = PyDict::new(py);
+        columns.set_item("source", column(nodes, |e| e.0)?)?;
+        columns.set_item("target", column(nodes, |e| e.1)?)?;
+        columns.set_item("layer_start", column(layers, |e| e.2)?)?;
+        columns.set_item("layer_end", column(layers, |e| e.3)?)?;
+        columns.set_item("layer_weight_index", column(None, |e| e.4)?)?;
+        columns.set_item(
+            "weight",
+            self.edges.iter().map(|e| e.5).collect::<Vec<f32>>(),
+        )?;
+        columns.set_item("metric", self.metric.clone())?;
+        py.import("pandas")?.getattr("DataFrame")?.call1((columns,))
+    }
+
+    /// The number of metric edges.
+    fn __len__(&self) -> usize {
+        self.metric.iter().filter(|m| **m).count()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "BackboneResult(n_edges={}, n_metric_edges={})",
+            self.edges.len(),
+            self.__len__()
+        )
+    }
+}
+
+/// Same as `backbone_py`, but returns a `BackboneResult`.
+#[pyfunction]
+fn backbone_result_py(
+    py: Python<'_>,
+    #[pyo3(from_py_with = "extract_edges")] edges: Vec<(usize, usize, usize, usize, usize, f32)>,
+) -> PyBackboneResult {
+    py.allow_threads(|| PyBackboneResult::new(edges))
+}
+
+/// The Pareto set of distances from `source` to `target` in an edge list,
+/// which may contain parallel edges.
+#[pyfunction]
+#[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
+fn pareto_distances_py(
+    py: Python<'_>,
+    #[pyo3(from_py_with = "extract_edges")] edges: Vec<(usize, usize, usize, usize, usize, f32)>,
+    source: NodeID,
+    target: NodeID,
+) -> Vec<PyMultiDistance> {
+    py.allow_threads(|| {
+        let graph = MultidistanceMultigraphHashmap::from_tuple_edge_list(&edges);
+        pareto_distances_between(&graph, source, target)
+    })
+    .into_iter()
+    .map(PyMultiDistance)
+    .collect()
+}
+
+#[pymodule]
+fn backbone(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_class::<PyMultiDistance>()?;
+    m.add_class::<PyMultidistanceGraph>()?;
+    m.add_class::<PyBackboneResult>()?;
+    m.add_function(wrap_pyfunction!(backbone_result_py, m)?)?;
+    m.add_function(wrap_pyfunction!(pareto_distances_py, m)?)?;
+    m.add_function(wrap_pyfunction!(distance_closure_py, m)?)?;
+    m.add_function(wrap_pyfunction!(backbone_py, m)?)?;
+    m.add_function(wrap_pyfunction!(distance_closure_labeled_py, m)?)?;
+    m.add_function(wrap_pyfunction!(backbone_labeled_py, m)?)?;
+    m.add_function(wrap_pyfunction!(distance_closure_named_py, m)?)?;
+    m.add_function(wrap_pyfunction!(backbone_named_py, m)?)?;
+    m.add_function(wrap_pyfunction!(merge_duplicate_edges_py, m)?)?;
+    m.add_function(wrap_pyfunction!(read_edge_list_py, m)?)?;
+    #[cfg(feature = "graphml")]
+    m.add_function(wrap_pyfunction!(read_graphml_py, m)?)?;
+    m.add_function(wrap_pyfunction!(save_graph_py, m)?)?;
+    m.add_function(wrap_pyfunction!(load_graph_py, m)?)?;
+    m.add_function(wrap_pyfunction!(save_closure_py, m)?)?;
+    m.add_function(wrap_pyfunction!(load_closure_py, m)?)?;
+    m.add_function(wrap_pyfunction!(save_backbone_py, m)?)?;
+    m.add_function(wrap_pyfunction!(load_backbone_py, m)?)?;
+    #[cfg(feature = "arrow")]
+    m.add_function(wrap_pyfunction!(distance_closure_arrow_py, m)?)?;
+    #[cfg(feature = "arrow")]
+    m.add_function(wrap_pyfunction!(write_closure_ipc_py, m)?)?;
+    #[cfg(feature = "parquet")]
+    m.add_function(wrap_pyfunction!(write_closure_parquet_py, m)?)?;
+    #[cfg(feature = "graphml")]
+    m.add_function(wrap_pyfunction!(write_backbone_graphml_py, m)?)?;
+    m.add_function(wrap_pyfunction!(coupled_edge_list_py, m)?)?;
+    m.add_function(wrap_pyfunction!(flatten_backbone_py, m)?)?;
+    m.add_function(wrap_pyfunction!(compare_backbones_py, m)?)?;
+    m.add_function(wrap_pyfunction!(multigraph_backbone_py, m)?)?;
+    m.add_function(wrap_pyfunction!(backbone_array_py, m)?)?;
+    m.add_function(wrap_pyfunction!(metric_mask_array_py, m)?)?;
+    m.add_function(wrap_pyfunction!(edges_from_networkx_py, m)?)?;
+    m.add_function(wrap_pyfunction!(backbone_networkx_py, m)?)?;
+    m.add_function(wrap_pyfunction!(distance_closure_undirected_py, m)?)?;
+    m.add_function(wrap_pyfunction!(backbone_undirected_py, m)?)?;
+    m.add_function(wrap_pyfunction!(structural_backbone_simas, m)?)?;
+    m.add_function(wrap_pyfunction!(structural_backbone_costa, m)?)?;
+    m.add_function(wrap_pyfunction!(structural_backbone_costa_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(structural_backbone_naive, m)?)?;
+
+    Ok(())
+}
+
+#[pyfunction]
+#[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
+fn distance_closure_py(
+    py: Python<'_>,
+    #[pyo3(from_py_with = "extract_edges")] edges: Vec<(usize, usize, usize, usize, usize, f32)>,
+) -> MultidistanceClosure {
+    py.allow_threads(|| distance_closure(&edges))
+}
+
+#[pyfunction]
+#[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
+fn backbone_py(
+    py: Python<'_>,
+    #[pyo3(from_py_with = "extract_edges")] edges: Vec<(usize, usize, usize, usize, usize, f32)>,
+) -> MultilayerBackbone {
+    py.allow_threads(|| multilayer_backbone(&edges))
+}
+
+#[pyfunction]
+#[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
+fn distance_closure_labeled_py(
+    py: Python<'_>,
+    edges: Vec<LabeledTupleEdge<String>>,
+) -> HashMap<String, HashMap<String, Vec<MultiDistance>>> {
+    py.allow_threads(|| distance_closure_labeled(&edges))
+}
+
+#[pyfunction]
+#[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
+fn backbone_labeled_py(
+    py: Python<'_>,
+    edges: Vec<LabeledTupleEdge<String>>,
+) -> HashMap<String, HashMap<String, Vec<MultiDistance>>> {
+    py.allow_threads(|| multilayer_backbone_labeled(&edges))
+}
+
+#[pyfunction]
+#[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
+#[allow(clippy::type_complexity)]
+fn distance_closure_named_py(
+    py: Python<'_>,
+    edges: Vec<NamedLayerTupleEdge>,
+) -> PyResult<HashMap<NodeID, HashMap<NodeID, Vec<HashMap<NamedEdgeLayer, f32>>>>> {
+    let (closure, layers) = py.allow_threads(|| distance_closure_named(&edges));
+    layers
+        .name_nested_map(&closure)
+        .map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+#[pyfunction]
+#[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
+#[allow(clippy::type_complexity)]
+fn backbone_named_py(
+    py: Python<'_>,
+    edges: Vec<NamedLayerTupleEdge>,
+) -> PyResult<HashMap<NodeID, HashMap<NodeID, Vec<HashMap<NamedEdgeLayer, f32>>>>> {
+    let (backbone, layers) = py.allow_threads(|| multilayer_backbone_named(&edges));
+    layers
+        .name_nested_map(&backbone)
+        .map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+/// Merges repeated rows of an edge list. `policy` is one of `error`, `first`,
+/// `last`, `min`, `sum` or `count`. Returns the merged edges and a summary of
+/// the rows that were merged.
+#[pyfunction]
+#[pyo3(signature = (edges, policy="last"))]
+#[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
+#[allow(clippy::type_complexity)]
+fn merge_duplicate_edges_py(
+    #[pyo3(from_py_with = "extract_edges")] edges: Vec<(usize, usize, usize, usize, usize, f32)>,
+    policy: &str,
+) -> PyResult<(
+    Vec<(usize, usize, usize, usize, usize, f32)>,
+    DuplicateSummary,
+)> {
+    let policy: DuplicatePolicy = policy
+        .parse()
+        .map_err(|e: ParseDuplicatePolicyError| PyValueError::new_err(e.to_string()))?;
+    merge_duplicate_edges(&edges, policy).map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+/// Reads a delimited edge list file. Columns are numbered from zero; `layer`
+/// (and `layer_end` for interlayer edges) give the columns of the layer names,
+/// otherwise every edge lies in layer `layer_name`. Without a `weight` column
+/// every row has weight 1.
+///
+/// Returns the edges, the node label of every node index, the name of every
+/// layer index and a summary of the rows that were merged, as returned by
+/// `merge_duplicate_edges_py`.
+#[pyfunction]
+#[pyo3(signature = (
+    path,
+    delimiter=None,
+    source=0,
+    target=1,
+    layer=None,
+    layer_end=None,
+    layer_name="0",
+    weight=None,
+    comment=Some("#"),
+    header=false,
+    duplicates="last",
+))]
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::type_complexity)]
+fn read_edge_list_py(
+    path: &str,
+    delimiter: Option<char>,
+    source: usize,
+    target: usize,
+    layer: Option<usize>,
+    layer_end: Option<usize>,
+    layer_name: &str,
+    weight: Option<usize>,
+    comment: Option<&str>,
+    header: bool,
+    duplicates: &str,
+) -> PyResult<(
+    Vec<(usize, usize, usize, usize, usize, f32)>,
+    Vec<String>,
+    Vec<String>,
+    DuplicateSummary,
+)> {
+    let layers = match (layer, layer_end) {
+        (None, None) => LayerColumns::Fixed(layer_name.to_string()),
+        (Some(column), None) => LayerColumns::Single(column),
+        (Some(start), Some(end)) => LayerColumns::Pair(start, end),
+        (None, Some(_)) => {
+            return Err(PyValueError::new_err("layer_end requires layer"));
+        }
+    };
+    let format = EdgeListFormat {
+        delimiter,
+        source_column: source,
+        target_column: target,
+        layers,
+        weight_column: weight,
+        comment_prefix: comment.map(str::to_string),
+        has_header: header,
+        duplicates: duplicates
+            .parse()
+            .map_err(|e: ParseDuplicatePolicyError| PyValueError::new_err(e.to_string()))?,
+        ..EdgeListFormat::default()
+    };
+
+    let mut nodes = NodeRegistry::new();
+    let mut layer_registry = LayerRegistry::new();
+    let (edges, summary) = read_edge_list_file(path, &format, &mut nodes, &mut layer_registry)
+        .map_err(|e| PyValueError::new_err(e.to_string()))?;
+    let layer_names = (0..layer_registry.n_layers())
+        .filter_map(|i| layer_registry.layer_name(i).map(str::to_string))
+        .collect();
+    Ok((
+        edges,
+        nodes.labels().cloned().collect(),
+        layer_names,
+        summary,
+    ))
+}
+
+/// Reads a GraphML file. The edge weight is the edge attribute named by
+/// `weight`, `distance` by default. The layer names are the edge attribute
+/// `layer` (and `layer_end` for interlayer edges) or the node attribute
+/// `node_layer`; otherwise every edge lies in layer `layer_name`. Edges with a
+/// `layer_weight` attribute, as written by `write_backbone_graphml_py`, keep
+/// that layer weight.
+///
+/// Returns the edges, the node id of every node index and the name of every
+/// layer index.
+#[cfg(feature = "graphml")]
+#[pyfunction]
+#[pyo3(signature = (path, weight="distance", layer=None, layer_end=None, node_layer=None, layer_name="0"))]
+#[allow(clippy::type_complexity)]
+fn read_graphml_py(
+    path: &str,
+    weight: &str,
+    layer: Option<String>,
+    layer_end: Option<String>,
+    node_layer: Option<String>,
+    layer_name: &str,
+) -> PyResult<(
+    Vec<(usize, usize, usize, usize, usize, f32)>,
+    Vec<String>,
+    Vec<String>,
+)> {
+    let layers = match (layer, layer_end, node_layer) {
+        (None, None, None) => GraphmlLayers::Fixed(layer_name.to_string()),
+        (Some(name), None, None) => GraphmlLayers::EdgeAttribute(name),
+        (Some(start), Some(end), None) => GraphmlLayers::EdgeAttributes(start, end),
+        (None, None, Some(name)) => GraphmlLayers::NodeAttribute(name),
+        _ => {
+            return Err(PyValueError::new_err(
+                "give either layer (and layer_end) or node_layer",
+            ))
+        }
+    };
+    let format = GraphmlFormat {
+        weight_attribute: weight.to_string(),
+        layers,
+        ..GraphmlFormat::default()
+    };
+
+    let mut nodes = NodeRegistry::new();
+    let mut layer_registry = LayerRegistry::new();
+    let file = std::fs::File::open(path)?;
+    let edges = read_graphml(
+        std::io::BufReader::new(file),
+        &format,
+        &mut nodes,
+        &mut layer_registry,
+    )
+    .map_err(|e| PyValueError::new_err(e.to_string()))?;
+    let layer_names = (0..layer_registry.n_layers())
+        .filter_map(|i| layer_registry.layer_name(i).map(str::to_string))
+        .collect();
+    Ok((edges, nodes.labels().cloned().collect(), layer_names))
+}
+
+/// Writes the edges with their backbone classification to a GraphML file.
+/// `nodes` and `layers` give the label of every node index and the name of
+/// every layer index; by default the indices themselves are used. All edges
+/// use the layer weight `weight`.
+#[cfg(feature = "graphml")]
+#[pyfunction]
+#[pyo3(signature = (path, edges, nodes=None, layers=None))]
+#[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
+fn write_backbone_graphml_py(
+    py: Python<'_>,
+    path: &str,
+    #[pyo3(from_py_with = "extract_edges")] edges: Vec<(usize, usize, usize, usize, usize, f32)>,
+    nodes: Option<Vec<String>>,
+    layers: Option<Vec<String>>,
+) -> PyResult<()> {
+    let n_nodes = edges.iter().map(|e| e.0.max(e.1) + 1).max().unwrap_or(0);
+    let n_layers = edges.iter().map(|e| e.2.max(e.3) + 1).max().unwrap_or(0);
+    let mut node_registry = NodeRegistry::new();
+    for label in nodes.unwrap_or_else(|| (0..n_nodes).map(|i| i.to_string()).collect()) {
+        node_registry.get_or_insert(label);
+    }
+    let layer_registry = LayerRegistry::from_layer_names(
+        &layers.unwrap_or_else(|| (0..n_layers).map(|i| i.to_string()).collect()),
+    );
+
+    let graph = MultidistanceMultigraphHashmap::from_tuple_edge_list(&edges);
+    let closure = py.allow_threads(|| multidistance_closure(&graph));
+    let file = std::fs::File::create(path)?;
+    write_backbone_graphml(
+        std::io::BufWriter::new(file),
+        &edges,
+        &closure,
+        &node_registry,
+        &layer_registry,
+    )
+    .map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+fn layer_registry_from_names(layers: Option<Vec<String>>) -> LayerRegistry {
+    layers.map_or_else(LayerRegistry::new, |names| {
+        LayerRegistry::from_layer_names(&names)
+    })
+}
+
+fn layer_names(layers: &LayerRegistry) -> Vec<String> {
+    (0..layers.n_layers())
+        .filter_map(|i| layers.layer_name(i).map(str::to_string))
+        .collect()
+}
+
+fn binary_format_error(e: BinaryFormatError) -> PyErr {
+    match e {
+        BinaryFormatError::Io(e) => e.into(),
+        e => PyValueError::new_err(e.to_string()),
+    }
+}
+
+/// Saves an edge list in the binary format, along with optional layer names.
+#[pyfunction]
+#[pyo3(signature = (path, edges, layers=None))]
+#[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
+fn save_graph_py(
+    path: &str,
+    #[pyo3(from_py_with = "extract_edges")] edges: Vec<(usize, usize, usize, usize, usize, f32)>,
+    layers: Option<Vec<String>>,
+) -> PyResult<()> {
+    let graph = MultidistanceMultigraphHashmap::from_tuple_edge_list(&edges);
+    let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    write_graph(file, &graph, &layer_registry_from_names(layers)).map_err(binary_format_error)
+}
+
+/// Loads a graph saved by `save_graph_py` as `(source, target, weight)` edges
+/// and the layer names.
+#[pyfunction]
+#[allow(clippy::type_complexity)]
+fn load_graph_py(path: &str) -> PyResult<(Vec<(NodeID, NodeID, MultiDistance)>, Vec<String>)> {
+    let file = std::io::BufReader::new(std::fs::File::open(path)?);
+    let (graph, layers): (MultidistanceMultigraphHashmap, _) =
+        read_graph(file).map_err(binary_format_error)?;
+    let edges = graph
+        .edge_list()
+        .map(|(source, target, weight)| (source, target, weight.clone()))
+        .collect();
+    Ok((edges, layer_names(&layers)))
+}
+
+/// Saves a closure as returned by `distance_closure_py` in the binary format.
+#[pyfunction]
+#[pyo3(signature = (path, closure, layers=None))]
+#[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
+fn save_closure_py(
+    path: &str,
+    closure: MultidistanceClosure,
+    layers: Option<Vec<String>>,
+) -> PyResult<()> {
+    let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    write_closure(file, &closure, &layer_registry_from_names(layers)).map_err(binary_format_error)
+}
+
+/// Loads a closure saved by `save_closure_py` and its layer names.
+#[pyfunction]
+fn load_closure_py(path: &str) -> PyResult<(MultidistanceClosure, Vec<String>)> {
+    let file = std::io::BufReader::new(std::fs::File::open(path)?);
+    let (closure, layers) = read_closure(file).map_err(binary_format_error)?;
+    Ok((closure, layer_names(&layers)))
+}
+
+/// Saves a backbone as returned by `backbone_py` in the binary format.
+#[pyfunction]
+#[pyo3(signature = (path, backbone, layers=None))]
+#[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
+fn save_backbone_py(
+    path: &str,
+    backbone: MultilayerBackbone,
+    layers: Option<Vec<String>>,
+) -> PyResult<()> {
+    let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    write_backbone(file, &backbone, &layer_registry_from_names(layers)).map_err(binary_format_error)
+}
+
+/// Loads a backbone saved by `save_backbone_py` and its layer names.
+#[pyfunction]
+fn load_backbone_py(path: &str) -> PyResult<(MultilayerBackbone, Vec<String>)> {
+    let file = std::io::BufReader::new(std::fs::File::open(path)?);
+    let (backbone, layers) = read_backbone(file).map_err(binary_format_error)?;
+    Ok((backbone, layer_names(&layers)))
+}
+
+/// Returns the closure of an edge list as a `pyarrow.RecordBatch` with one row
+/// per Pareto distance (see `closure_record_batch`). The batch is handed over
+/// through the Arrow C data interface, so the columns are not copied.
+#[cfg(feature = "arrow")]
+#[pyfunction]
+#[pyo3(signature = (edges, layers=None))]
+#[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
+fn distance_closure_arrow_py(
+    py: Python<'_>,
+    #[pyo3(from_py_with = "extract_edges")] edges: Vec<(usize, usize, usize, usize, usize, f32)>,
+    layers: Option<Vec<String>>,
+) -> PyResult<PyObject> {
+    use arrow_array::ffi::to_ffi;
+    use arrow_array::{Array, StructArray};
+
+    let batch = closure_record_batch(
+        &py.allow_threads(|| distance_closure(&edges)),
+        &layer_registry_from_names(layers),
+    )
+    .map_err(|e| PyValueError::new_err(e.to_string()))?;
+    let (array, schema) = to_ffi(&StructArray::from(batch).into_data())
+        .map_err(|e| PyValueError::new_err(e.to_string()))?;
+    // pyarrow moves the array out of `array` and releases `schema`, leaving
+    // both with a null release callback, so dropping them afterwards is a no-op
+    let array = Box::new(array);
+    let schema = Box::new(schema);
+    let batch = py.import("pyarrow")?.getattr("RecordBatch")?.call_method1(
+        "_import_from_c",
+        (
+            std::ptr::addr_of!(*array) as usize,
+            std::ptr::addr_of!(*schema) as usize,
+        ),
+    )?;
+    Ok(batch.into())
+}
+
+/// Writes the closure of an edge list to an Arrow IPC (Feather) file.
+#[cfg(feature = "arrow")]
+#[pyfunction]
+#[pyo3(signature = (path, edges, layers=None))]
+#[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
+fn write_closure_ipc_py(
+    py: Python<'_>,
+    path: &str,
+    #[pyo3(from_py_with = "extract_edges")] edges: Vec<(usize, usize, usize, usize, usize, f32)>,
+    layers: Option<Vec<String>>,
+) -> PyResult<()> {
+    let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    write_closure_ipc(
+        file,
+        &py.allow_threads(|| distance_closure(&edges)),
+        &layer_registry_from_names(layers),
+    )
+    .map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+/// Writes the closure of an edge list to a Parquet file.
+#[cfg(feature = "parquet")]
+#[pyfunction]
+#[pyo3(signature = (path, edges, layers=None))]
+#[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
+fn write_closure_parquet_py(
+    py: Python<'_>,
+    path: &str,
+    #[pyo3(from_py_with = "extract_edges")] edges: Vec<(usize, usize, usize, usize, usize, f32)>,
+    layers: Option<Vec<String>>,
+) -> PyResult<()> {
+    let file = std::fs::File::create(path)?;
+    write_closure_parquet(
+        file,
+        &py.allow_threads(|| distance_closure(&edges)),
+        &layer_registry_from_names(layers),
+    )
+    .map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+/// Returns the supra-graph edge list built from one edge list per layer, and
+/// the `(node, layer)` pair of every supra-graph node. `identity_edge_weight`
+/// couples every pair of layers, except the pairs in `layer_pair_weights`;
+/// `node_weights` cannot be combined with the others. Without any coupling
+/// argument, layers are not coupled.
+#[pyfunction]
+#[pyo3(signature = (layers, identity_edge_weight=None, layer_pair_weights=None, node_weights=None))]
+#[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
+#[allow(clippy::type_complexity)]
+fn coupled_edge_list_py(
+    layers: Vec<Vec<(usize, usize, f32)>>,
+    identity_edge_weight: Option<f32>,
+    layer_pair_weights: Option<HashMap<(usize, usize), f32>>,
+    node_weights: Option<HashMap<usize, f32>>,
+) -> PyResult<(
+    Vec<(usize, usize, usize, usize, usize, f32)>,
+    Vec<(usize, usize)>,
+)> {
+    let coupling = match (identity_edge_weight, layer_pair_weights, node_weights) {
+        (None, None, None) => CouplingPolicy::None,
+        (Some(weight), None, None) => CouplingPolicy::Uniform(weight),
+        (default, Some(weights), None) => CouplingPolicy::PerLayerPair { weights, default },
+        (None, None, Some(weights)) => CouplingPolicy::PerNode(
+            weights
+                .into_iter()
+                .map(|(node, weight)| (NodeID(node), weight))
+                .collect(),
+        ),
+        _ => {
+            return Err(PyValueError::new_err(
+                "node_weights cannot be combined with other coupling arguments",
+            ))
+        }
+    };
+    let (edges, registry) = coupled_tuple_edge_list(&layers, &coupling);
+    let state_nodes = registry.labels().map(|s| (s.node.0, s.layer)).collect();
+    Ok((edges, state_nodes))
+}
+
+/// Collapses the backbone of a supra-graph edge list onto its physical nodes.
+/// `state_nodes` gives the `(node, layer)` pair of every supra-graph node, as
+/// returned by `coupled_edge_list_py`.
+#[pyfunction]
+#[pyo3(signature = (edges, state_nodes, selection="union", aggregation="min"))]
+#[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
+fn flatten_backbone_py(
+    py: Python<'_>,
+    #[pyo3(from_py_with = "extract_edges")] edges: Vec<(usize, usize, usize, usize, usize, f32)>,
+    state_nodes: Vec<(usize, usize)>,
+    selection: &str,
+    aggregation: &str,
+) -> PyResult<Vec<WeightedEdge>> {
+    let selection: EdgeSelection = selection
+        .parse()
+        .map_err(|e: ParseAggregationError| PyValueError::new_err(e.to_string()))?;
+    let aggregation: WeightAggregation = aggregation
+        .parse()
+        .map_err(|e: ParseAggregationError| PyValueError::new_err(e.to_string()))?;
+
+    let mut registry = NodeRegistry::new();
+    for (node, layer) in state_nodes {
+        registry.get_or_insert(StateNode {
+            node: NodeID(node),
+            layer,
+        });
+    }
+    let graph = MultidistanceGraphHashmap::from_tuple_edge_list(&edges);
+    if let Some(node) = graph
+        .nodes()
+        .into_iter()
+        .find(|n| registry.label(*n).is_none())
+    {
+        return Err(PyValueError::new_err(format!("{node} has no state node")));
+    }
+    Ok(py.allow_threads(|| {
+        let backbone = multilayer_backbone(&edges);
+        flatten_backbone(&graph, &backbone, &registry, selection, aggregation)
+    }))
+}
+
+#[pyfunction]
+#[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
+fn compare_backbones_py(
+    py: Python<'_>,
+    #[pyo3(from_py_with = "extract_edges")] edges: Vec<(usize, usize, usize, usize, usize, f32)>,
+) -> BackboneComparison {
+    py.allow_threads(|| {
+        let graph = MultidistanceGraphHashmap::from_tuple_edge_list(&edges);
+        compare_backbones(&graph)
+    })
+}
+
+#[pyfunction]
+#[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
+fn multigraph_backbone_py(
+    py: Python<'_>,
+    #[pyo3(from_py_with = "extract_edges")] edges: Vec<(usize, usize, usize, usize, usize, f32)>,
+) -> Vec<(NodeID, NodeID, MultiDistance)> {
+    py.allow_threads(|| multigraph_backbone(&edges))
+}
+
+/// Converts `array` to a contiguous NumPy array of `dtype` and returns its
+/// shape and raw bytes. `kinds` lists the accepted NumPy dtype kinds, so that
+/// e.g. float node ids are not silently truncated. Going through `tobytes`
+/// keeps us on the stable ABI, which has no buffer protocol before 3.11; the
+/// bytes are borrowed from the Python object rather than copied again.
+fn numpy_bytes<'py>(
+    py: Python<'py>,
+    array: &'py PyAny,
+    dtype: &str,
+    kinds: &str,
+) -> PyResult<(Vec<usize>, &'py [u8])> {
+    let numpy = py.import("numpy")?;
+    let array = numpy.call_method1("asarray", (array,))?;
+    let kind: String = array.getattr("dtype")?.getattr("kind")?.extract()?;
+    if !kinds.contains(kind.as_str()) {
+        return Err(PyValueError::new_err(format!(
+            "unsupported array dtype {}",
+            array.getattr("dtype")?
+        )));
+    }
+    let array = numpy.call_method1("ascontiguousarray", (array, dtype))?;
+    let shape = array.getattr("shape")?.extract()?;
+    let bytes = array.call_method0("tobytes")?.extract()?;
+    Ok((shape, bytes))
+}
+
+/// Reads an integer array of shape `(n, 5)` with the columns source, target,
+/// layer start, layer end and layer weight index, and a weight array of shape
+/// `(n,)`, into tuple edges.
+#[allow(clippy::type_complexity)]
+fn edges_from_arrays(
+    py: Python<'_>,
+    edges: &PyAny,
+    weights: &PyAny,
+) -> PyResult<Vec<(usize, usize, usize, usize, usize, f32)>> {
+    let (edge_shape, edge_bytes) = numpy_bytes(py, edges, "<i8", "iu")?;
+    let (weight_shape, weight_bytes) = numpy_bytes(py, weights, "<f4", "iuf")?;
+    let n_edges = match (edge_shape.as_slice(), weight_shape.as_slice()) {
+        ([n, 5], [m]) if n == m => *n,
+        _ => {
+            return Err(PyValueError::new_err(format!(
+                "expected edges of shape (n, 5) and weights of shape (n,), got {edge_shape:?} and {weight_shape:?}"
+            )))
+        }
+    };
+
+    let id = |bytes: &[u8]| {
+        let id = i64::from_le_bytes(bytes.try_into().unwrap());
+        usize::try_from(id)
+            .map_err(|_| PyValueError::new_err(format!("negative node or layer id {id}")))
+    };
+    let mut tuples = Vec::with_capacity(n_edges);
+    for (row, weight) in edge_bytes
+        .chunks_exact(40)
+        .zip(weight_bytes.chunks_exact(4))
+    {
+        tuples.push((
+            id(&row[0..8])?,
+            id(&row[8..16])?,
+            id(&row[16..24])?,
+            id(&row[24..32])?,
+            id(&row[32..40])?,
+            f32::from_le_bytes(weight.try_into().unwrap()),
+        ));
+    }
+    Ok(tuples)
+}
+
+/// Extracts the `edges` argument of the functions that take tuple edges. Next
+/// to a sequence of `(source, target, layer start, layer end, layer weight
+/// index, weight)` tuples, this accepts a pair `(edges, weights)` of NumPy
+/// arrays in the layout of `backbone_array_py`.
+#[allow(clippy::type_complexity)]
+fn extract_edges(edges: &PyAny) -> PyResult<Vec<(usize, usize, usize, usize, usize, f32)>> {
+    if let Ok(pair) = edges.downcast::<PyTuple>() {
+        if pair.len() == 2 && pair.get_item(0)?.hasattr("shape")? {
+            return edges_from_arrays(edges.py(), pair.get_item(0)?, pair.get_item(1)?);
+        }
+    }
+    edges.extract()
+}
+
+/// Builds a writable NumPy array of `dtype` and `shape` from raw bytes.
+fn numpy_array(py: Python<'_>, bytes: &[u8], dtype: &str, shape: &[usize]) -> PyResult<PyObject> {
+    let numpy = py.import("numpy")?;
+    // a bytearray rather than bytes, so that the array is not read-only
+    let buffer = pyo3::types::PyByteArray::new(py, bytes);
+    let array = numpy
+        .call_method1("frombuffer", (buffer, dtype))?
+        .call_method1("reshape", (shape.to_vec(),))?;
+    Ok(array.into())
+}
+
+/// Array version of `multigraph_backbone_py`. `edges` is an integer array of
+/// shape `(n, 5)` with the columns source, target, layer start, layer end and
+/// layer weight index, and `weights` holds the edge weights. Returns the rows
+/// of both that are metric edges.
+#[pyfunction]
+fn backbone_array_py(
+    py: Python<'_>,
+    edges: &PyAny,
+    weights: &PyAny,
+) -> PyResult<(PyObject, PyObject)> {
+    let tuples = edges_from_arrays(py, edges, weights)?;
+    let mask = py.allow_threads(|| multigraph_metric_mask(&tuples));
+
+    let mut edge_bytes = Vec::new();
+    let mut weight_bytes = Vec::new();
+    for (edge, _) in tuples.iter().zip(mask).filter(|(_, metric)| *metric) {
+        for id in [edge.0, edge.1, edge.2, edge.3, edge.4] {
+            edge_bytes.extend((id as i64).to_le_bytes());
+        }
+        weight_bytes.extend(edge.5.to_le_bytes());
+    }
+    let n_edges = weight_bytes.len() / 4;
+    Ok((
+        numpy_array(py, &edge_bytes, "<i8", &[n_edges, 5])?,
+        numpy_array(py, &weight_bytes, "<f4", &[n_edges])?,
+    ))
+}
+
+/// Same as `backbone_array_py`, but returns a boolean array that tells for
+/// every row whether it is a metric edge.
+#[pyfunction]
+fn metric_mask_array_py(py: Python<'_>, edges: &PyAny, weights: &PyAny) -> PyResult<PyObject> {
+    let tuples = edges_from_arrays(py, edges, weights)?;
+    let mask: Vec<u8> = py
+        .allow_threads(|| multigraph_metric_mask(&tuples))
+        .into_iter()
+        .map(u8::from)
+        .collect();
+    numpy_array(py, &mask, "?", &[mask.len()])
+}
+
+/// The edges of a networkx graph in tuple form. Nodes and layer labels are
+/// numbered in order of appearance.
+struct NetworkxEdges<'py> {
+    /// The node of every node index.
+    nodes: &'py PyList,
+    /// The layer label of every layer index.
+    layers: &'py PyList,
+    /// One edge per networkx edge, followed by the reversed edges of an
+    /// undirected graph.
+    edges: Vec<(usize, usize, usize, usize, usize, f32)>,
+    /// The `(u, v, data)` or `(u, v, key, data)` tuple of the networkx edges,
+    /// in the order of `edges`.
+    rows: Vec<&'py PyTuple>,
+}
+
+/// Reads the edges of a networkx (Multi)(Di)Graph. The `layer` attribute of
+/// an edge is either a layer label or a `(start, end)` tuple of labels for an
+/// interlayer edge; without it the edge lies in layer `None`. Edges without
+/// the `weight` attribute have weight 1. All weights use layer weight index 0,
+/// since there is a single weight attribute. Undirected edges are used in both
+/// directions with the same `(start, end)` layers, like in
+/// `UndirectedMultidistanceGraphHashmap`, so that an interlayer edge counts
+/// towards the same Pareto dimension whichever way it is traversed.
+fn read_networkx<'py>(
+    py: Python<'py>,
+    graph: &'py PyAny,
+    weight: &str,
+    layer: &str,
+) -> PyResult<NetworkxEdges<'py>> {
+    let node_index = PyDict::new(py);
+    let nodes = PyList::empty(py);
+    for node in graph.getattr("nodes")?.iter()? {
+        let node = node?;
+        node_index.set_item(node, nodes.len())?;
+        nodes.append(node)?;
+    }
+    let index = |node: &PyAny| -> PyResult<usize> {
+        node_index
+            .get_item(node)?
+            .ok_or_else(|| PyValueError::new_err(format!("unknown node {node}")))?
+            .extract()
+    };
+
+    let layer_index = PyDict::new(py);
+    let layers = PyList::empty(py);
+    let intern_layer = |label: &PyAny| -> PyResult<usize> {
+        if let Some(i) = layer_index.get_item(label)? {
+            return i.extract();
+        }
+        let i = layers.len();
+        layer_index.set_item(label, i)?;
+        layers.append(label)?;
+        Ok(i)
+    };
+
+    let kwargs = PyDict::new(py);
+    kwargs.set_item("data", true)?;
+    if graph.call_method0("is_multigraph")?.extract()? {
+        kwargs.set_item("keys", true)?;
+    }
+    let mut edges = Vec::new();
+    let mut rows = Vec::new();
+    for row in graph.getattr("edges")?.call((), Some(kwargs))?.iter()? {
+        let row: &PyTuple = row?.downcast()?;
+        let data: &PyDict = row.get_item(row.len() - 1)?.downcast()?;
+        let edge_weight: f32 = match data.get_item(weight)? {
+            Some(value) => value.extract()?,
+            None => 1.0,
+        };
+        if !(edge_weight >= 0.0 && edge_weight.is_finite()) {
+            return Err(PyValueError::new_err(format!(
+                "weights must be finite and non-negative, not {edge_weight}"
+            )));
+        }
+        let (layer_start, layer_end) = match data.get_item(layer)? {
+            Some(label) => match label.extract::<(&PyAny, &PyAny)>() {
+                Ok((start, end)) => (intern_layer(start)?, intern_layer(end)?),
+                Err(_) => {
+                    let layer = intern_layer(label)?;
+                    (layer, layer)
+                }
+            },
+            None => {
+                let layer = intern_layer(py.None().into_ref(py))?;
+                (layer, layer)
+            }
+        };
+        edges.push((
+            index(row.get_item(0)?)?,
+            index(row.get_item(1)?)?,
+            layer_start,
+            layer_end,
+            0,
+            edge_weight,
+        ));
+        rows.push(row);
+    }
+
+    if !graph.call_method0("is_directed")?.extract::<bool>()? {
+        let reversed: Vec<_> = edges
+            .iter()
+            .filter(|edge| edge.0 != edge.1)
+            .map(|&(u, v, start, end, i, w)| (v, u, start, end, i, w))
+            .collect();
+        edges.extend(reversed);
+    }
+
+    Ok(NetworkxEdges {
+        nodes,
+        layers,
+        edges,
+        rows,
+    })
+}
+
+/// Converts a networkx graph (see `backbone_networkx_py` for the attributes)
+/// to tuple edges. Returns the edges, the node of every node index and the
+/// layer label of every layer index. Undirected edges are listed in both
+/// directions, with the same layers.
+#[pyfunction]
+#[pyo3(signature = (graph, weight="weight", layer="layer"))]
+#[allow(clippy::type_complexity)]
+fn edges_from_networkx_py<'py>(
+    py: Python<'py>,
+    graph: &'py PyAny,
+    weight: &str,
+    layer: &str,
+) -> PyResult<(
+    Vec<(usize, usize, usize, usize, usize, f32)>,
+    &'py PyList,
+    &'py PyList,
+)> {
+    let NetworkxEdges {
+        nodes,
+        layers,
+        edges,
+        ..
+    } = read_networkx(py, graph, weight, layer)?;
+    Ok((edges, nodes, layers))
+}
+
+/// Returns the backbone of a networkx graph as a graph of the same class, with
+/// the original nodes, edge keys and attributes. The edge attribute `weight`
+/// holds the distance and `layer` the layer label, or a `(start, end)` tuple
+/// of labels for interlayer edges. Every backbone edge is annotated with the
+/// `pareto` attribute: the Pareto set of distances between its endpoints, as a
+/// list of `{(start, end): distance}` dicts. Pass `pareto=None` to skip it.
+/// There is one weight attribute, so all distances use layer weight index 0
+/// and the `pareto` keys leave it out. In an undirected graph, an interlayer
+/// edge has the layers `(start, end)` in both directions.
+#[pyfunction]
+#[pyo3(signature = (graph, weight="weight", layer="layer", pareto=Some("pareto")))]
+fn backbone_networkx_py<'py>(
+    py: Python<'py>,
+    graph: &'py PyAny,
+    weight: &str,
+    layer: &str,
+    pareto: Option<&str>,
+) -> PyResult<&'py PyAny> {
+    let NetworkxEdges {
+        layers,
+        edges,
+        rows,
+        ..
+    } = read_networkx(py, graph, weight, layer)?;
+    let closure = py.allow_threads(|| {
+        multidistance_closure(&MultidistanceMultigraphHashmap::from_tuple_edge_list(
+            &edges,
+        ))
+    });
+
+    let backbone = graph.getattr("__class__")?.call0()?;
+    backbone
+        .getattr("graph")?
+        .call_method1("update", (graph.getattr("graph")?,))?;
+    let kwargs = PyDict::new(py);
+    kwargs.set_item("data", true)?;
+    let nodes = graph.getattr("nodes")?.call((), Some(kwargs))?;
+    backbone.call_method1("add_nodes_from", (nodes,))?;
+
+    for (&(source, target, layer_start, layer_end, layer_weight_index, edge_weight), row) in
+        edges.iter().zip(rows)
+    {
+        let distances = closure
+            .get(&NodeID(source))
+            .and_then(|targets| targets.get(&NodeID(target)))
+            .map_or(&[][..], Vec::as_slice);
+        let distance =
+            MultiDistance::from_tuple(layer_start, layer_end, layer_weight_index, edge_weight);
+        if !distances.contains(&distance) {
+            continue;
+        }
+
+        let data: &PyDict = row.get_item(row.len() - 1)?.downcast::<PyDict>()?.copy()?;
+        if let Some(pareto) = pareto {
+            let annotation = PyList::empty(py);
+            for distance in distances {
+                let entries = PyDict::new(py);
+                for (edge_layer, value) in &distance.total {
+                    let key = (
+                        layers.get_item(edge_layer.layer_start)?,
+                        layers.get_item(edge_layer.layer_end)?,
+                    );
+                    entries.set_item(key, value)?;
+                }
+                annotation.append(entries)?;
+            }
+            data.set_item(pareto, annotation)?;
+        }
+        // `row` is `(u, v, data)`, or `(u, v, key, data)` for multigraphs
+        let endpoints = row.get_slice(0, row.len() - 1);
+        backbone.call_method("add_edge", endpoints, Some(data))?;
+    }
+    Ok(backbone)
+}
+
+#[pyfunction]
+#[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
+fn distance_closure_undirected_py(
+    py: Python<'_>,
+    #[pyo3(from_py_with = "extract_edges")] edges: Vec<(usize, usize, usize, usize, usize, f32)>,
+) -> MultidistanceClosure {
+    py.allow_threads(|| distance_closure_undirected(&edges))
+}
+
+#[pyfunction]
+#[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
+fn backbone_undirected_py(
+    py: Python<'_>,
+    #[pyo3(from_py_with = "extract_edges")] edges: Vec<(usize, usize, usize, usize, usize, f32)>,
+) -> MultilayerBackbone {
+    py.allow_threads(|| multilayer_backbone_undirected(&edges))
+}
+
+#[pyfunction]
+#[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
+fn structural_backbone_simas(
+    py: Python<'_>,
+    #[pyo3(from_py_with = "extract_edges")] edges: Vec<(usize, usize, usize, usize, usize, f32)>,
+) -> HashMap<NodeID, HashMap<NodeID, MultiDistance>> {
+    py.allow_threads(|| {
+        let mut graph = MultidistanceGraphHashmap::from_tuple_edge_list(&edges);
+        fast_backbone_simas(&mut graph);
+        graph.edges
+    })
+}
+
+#[pyfunction]
+#[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
+fn structural_backbone_costa(
+    py: Python<'_>,
+    #[pyo3(from_py_with = "extract_edges")] edges: Vec<(usize, usize, usize, usize, usize, f32)>,
+) -> HashMap<NodeID, HashMap<NodeID, MultiDistance>> {
+    py.allow_threads(|| {
+        let mut graph = MultidistanceGraphHashmap::from_tuple_edge_list(&edges);
+        fast_backbone_costa(&mut graph);
+        graph.edges
+    })
+}
+
+#[pyfunction]
+#[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
+fn structural_backbone_costa_parallel(
+    py: Python<'_>,
+    #[pyo3(from_py_with = "extract_edges")] edges: Vec<(usize, usize, usize, usize, usize, f32)>,
+) -> HashMap<NodeID, HashMap<NodeID, MultiDistance>> {
+    py.allow_threads(|| {
+        let mut graph = MultidistanceGraphHashmap::from_tuple_edge_list(&edges);
+        fast_backbone_costa_parallel(&mut graph);
+        graph.edges
+    })
+}
+
+#[pyfunction]
+#[allow(clippy::needless_pass_by_value)] // this makes it easier to deal with pyO3
+fn structural_backbone_naive(
+    py: Python<'_>,
+    #[pyo3(from_py_with = "extract_edges")] edges: Vec<(usize, usize, usize, usize, usize, f32)>,
+) -> HashMap<NodeID, HashMap<NodeID, MultiDistance>> {
+    py.allow_threads(|| {
+        let mut graph = MultidistanceGraphHashmap::from_tuple_edge_list(&edges);
+        structural_backbone(&mut graph, None);
+        graph.edges
+    })
+}
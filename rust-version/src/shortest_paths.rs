@@ -3,7 +3,8 @@ use crate::{
     multigraph::MultidistanceGraph,
 };
 
-use std::collections::{HashMap, VecDeque};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
 
 struct FringeNode {
     node_id: NodeID,
@@ -86,10 +87,244 @@ pub fn parteto_shortest_distance_from_source(
     dist_map
 }
 
+/// A partial path in the queue of [`pareto_distances_between`]. The queue pops
+/// the path with the smallest total weight first. `steps` is the number of
+/// edges of the path, if the search counts them.
+struct QueuedPath {
+    node_id: NodeID,
+    dist: MultiDistance,
+    steps: Option<usize>,
+    total: f32,
+}
+
+impl QueuedPath {
+    fn new(node_id: NodeID, dist: MultiDistance, steps: Option<usize>) -> QueuedPath {
+        let total = dist.total.values().sum();
+        QueuedPath {
+            node_id,
+            dist,
+            steps,
+            total,
+        }
+    }
+}
+
+impl PartialEq for QueuedPath {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for QueuedPath {}
+
+impl PartialOrd for QueuedPath {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QueuedPath {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // reversed, since `BinaryHeap` is a max-heap
+        other.total.total_cmp(&self.total)
+    }
+}
+
+fn is_dominated(dists: Option<&Vec<MultiDistance>>, dist: &MultiDistance) -> bool {
+    dists.is_some_and(|dists| dists.iter().any(|d| d <= dist))
+}
+
+/// The Pareto set of distances from `source` to `target`, which is empty if
+/// `target` cannot be reached and the zero distance if `source == target`.
+///
+/// Unlike [`parteto_shortest_distance_from_source`], which finds the distances
+/// to every node, this drops every partial path that is no shorter than a
+/// distance to `target` found so far, and stops as soon as no partial path is
+/// left. Paths with a small total weight are extended first, so that the
+/// distances to `target` that prune the search are found early. This relies on
+/// the edge weights being non-negative.
+#[must_use]
+// `!(a <= b)` is not `a > b` for the partial order of distances
+#[allow(clippy::neg_cmp_op_on_partial_ord)]
+pub fn pareto_distances_between(
+    graph: &impl MultidistanceGraph,
+    source: NodeID,
+    target: NodeID,
+) -> Vec<MultiDistance> {
+    if source == target {
+        return vec![MultiDistance::default()];
+    }
+
+    // the Pareto set of the partial paths to every node found so far
+    let mut dist_map = HashMap::from([(source, vec![MultiDistance::default()])]);
+    let mut queue = BinaryHeap::from([QueuedPath::new(source, MultiDistance::default(), None)]);
+
+    while let Some(QueuedPath { node_id, dist, .. }) = queue.pop() {
+        // paths to the target are not extended, and the others may have been
+        // dominated since they were queued
+        if node_id == target
+            || !dist_map.get(&node_id).is_some_and(|d| d.contains(&dist))
+            || is_dominated(dist_map.get(&target), &dist)
+        {
+            continue;
+        }
+
+        for (child, edge) in graph.neighbor_edges(&node_id) {
+            let child_dist = dist.clone() + edge;
+            if is_dominated(dist_map.get(&target), &child_dist)
+                || is_dominated(dist_map.get(&child), &child_dist)
+            {
+                continue;
+            }
+            let child_dists = dist_map.entry(child).or_default();
+            child_dists.retain(|d| !(child_dist <= *d));
+            child_dists.push(child_dist.clone());
+            queue.push(QueuedPath::new(child, child_dist, None));
+        }
+    }
+
+    dist_map.remove(&target).unwrap_or_default()
+}
+
+/// One direction of [`pareto_distances_bidirectional`]: the Pareto set of the
+/// partial paths found so far to (or from) every node, with their number of
+/// steps, and the partial paths that still have to be extended.
+struct SearchFront {
+    dist_map: HashMap<NodeID, Vec<(MultiDistance, usize)>>,
+    queue: BinaryHeap<QueuedPath>,
+    max_steps: Option<usize>,
+}
+
+impl SearchFront {
+    fn new(start: NodeID, max_steps: Option<usize>) -> SearchFront {
+        SearchFront {
+            dist_map: HashMap::from([(start, vec![(MultiDistance::default(), 0)])]),
+            queue: BinaryHeap::from([QueuedPath::new(start, MultiDistance::default(), Some(0))]),
+            max_steps,
+        }
+    }
+
+    /// With a step limit, a shorter path only dominates a path with at least
+    /// as many steps, since the other one may be the only one that can still
+    /// be extended.
+    fn dominates(
+        &self,
+        (a, a_steps): (&MultiDistance, usize),
+        (b, b_steps): (&MultiDistance, usize),
+    ) -> bool {
+        a <= b && (self.max_steps.is_none() || a_steps <= b_steps)
+    }
+
+    fn insert(&mut self, node_id: NodeID, dist: MultiDistance, steps: usize) {
+        let known = self.dist_map.get(&node_id).map_or(&[][..], Vec::as_slice);
+        if known
+            .iter()
+            .any(|(d, s)| self.dominates((d, *s), (&dist, steps)))
+        {
+            return;
+        }
+        let mut known = self.dist_map.remove(&node_id).unwrap_or_default();
+        known.retain(|(d, s)| !self.dominates((&dist, steps), (d, *s)));
+        known.push((dist.clone(), steps));
+        self.dist_map.insert(node_id, known);
+        self.queue.push(QueuedPath::new(node_id, dist, Some(steps)));
+    }
+
+    /// Whether a queued path has not been dominated since it was queued.
+    fn is_current(&self, path: &QueuedPath) -> bool {
+        self.dist_map.get(&path.node_id).is_some_and(|known| {
+            known
+                .iter()
+                .any(|(d, s)| *d == path.dist && Some(*s) == path.steps)
+        })
+    }
+}
+
+/// Same as [`pareto_distances_between`], but searches forward from `source`
+/// and backward from `target` at the same time, extending whichever side has
+/// fewer pending paths, and joins the two wherever they meet. Every distance
+/// to `target` found this way prunes the partial paths of both sides.
+///
+/// Only paths of at most `max_depth` edges are considered. With `bound`, only
+/// distances no longer than `bound` are searched for, and the search stops as
+/// soon as one strictly shorter than `bound` is found; this is what
+/// [`crate::is_metric_in_n_steps`] needs.
+#[must_use]
+#[allow(clippy::neg_cmp_op_on_partial_ord)]
+pub fn pareto_distances_bidirectional(
+    graph: &impl MultidistanceGraph,
+    source: NodeID,
+    target: NodeID,
+    max_depth: Option<usize>,
+    bound: Option<&MultiDistance>,
+) -> Vec<MultiDistance> {
+    if source == target {
+        return vec![MultiDistance::default()];
+    }
+
+    // a path of at most `n` steps is a forward path of at most `n / 2`
+    // steps, rounded up, followed by a backward path of at most `n / 2` steps
+    let mut forward = SearchFront::new(source, max_depth.map(|n| n.div_ceil(2)));
+    let mut backward = SearchFront::new(target, max_depth.map(|n| n / 2));
+    let mut found: Vec<MultiDistance> = Vec::new();
+    let is_pruned = |found: &[MultiDistance], dist: &MultiDistance| {
+        found.iter().any(|f| f <= dist) || bound.is_some_and(|b| !(dist <= b))
+    };
+
+    loop {
+        let is_forward = match (forward.queue.len(), backward.queue.len()) {
+            (0, 0) => break,
+            (f, b) => f != 0 && (b == 0 || f <= b),
+        };
+        let (front, other) = if is_forward {
+            (&mut forward, &backward)
+        } else {
+            (&mut backward, &forward)
+        };
+        let Some(path) = front.queue.pop() else {
+            break;
+        };
+        if !front.is_current(&path) || is_pruned(&found, &path.dist) {
+            continue;
+        }
+
+        // complete the path with every path of the other side through the same node
+        for (other_dist, _) in other.dist_map.get(&path.node_id).into_iter().flatten() {
+            let dist = path.dist.clone() + other_dist.clone();
+            if !is_pruned(&found, &dist) {
+                found.retain(|f| !(dist <= *f));
+                found.push(dist);
+            }
+        }
+        if bound.is_some_and(|b| found.iter().any(|f| f < b)) {
+            break;
+        }
+
+        let steps = path.steps.expect("the search fronts count steps");
+        if front.max_steps.is_some_and(|n| steps >= n) {
+            continue;
+        }
+        let edges = if is_forward {
+            graph.neighbor_edges(&path.node_id)
+        } else {
+            graph.in_neighbor_edges(&path.node_id)
+        };
+        for (next, edge) in edges {
+            let dist = path.dist.clone() + edge;
+            if !is_pruned(&found, &dist) {
+                front.insert(next, dist, steps + 1);
+            }
+        }
+    }
+
+    found
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::multigraph::MultidistanceGraphHashmap;
+    use crate::multigraph::{MultidistanceGraphHashmap, UndirectedMultidistanceGraphHashmap};
+    use proptest::prelude::*;
     #[test]
     fn test_simple_single_layer_shortest_path() {
         let m01 = MultiDistance::from_tuple(0, 0, 0, 2.0);
@@ -153,4 +388,148 @@ mod tests {
 
         assert_eq!(expected, shortest_paths);
     }
+
+    #[test]
+    fn test_pareto_distances_between() {
+        let graph = MultidistanceGraphHashmap::from_tuple_edge_list(&[
+            (0, 1, 0, 0, 0, 1.0),
+            (0, 3, 0, 1, 0, 2.0),
+            (1, 2, 0, 1, 0, 1.0),
+            (2, 3, 1, 1, 0, 1.0),
+            (3, 4, 1, 1, 0, 1.0),
+        ]);
+
+        // two incomparable paths, as in `test_simple_multilayer_shortest_path`
+        let dists = pareto_distances_between(&graph, NodeID(0), NodeID(3));
+        assert_eq!(dists.len(), 2);
+        assert!(dists.contains(&MultiDistance::from_tuple(0, 1, 0, 2.0)));
+
+        assert!(pareto_distances_between(&graph, NodeID(3), NodeID(0)).is_empty());
+        assert_eq!(
+            pareto_distances_between(&graph, NodeID(2), NodeID(2)),
+            vec![MultiDistance::default()]
+        );
+    }
+
+    type TupleEdge = (usize, usize, usize, usize, usize, f32);
+
+    /// The number of nodes and a random edge list on them, with intralayer
+    /// edges in up to three layers so that Pareto sets have several elements.
+    fn edge_list() -> impl Strategy<Value = (usize, Vec<TupleEdge>)> {
+        (2..8_usize).prop_flat_map(|n_nodes| {
+            let weight = prop::sample::select(vec![0.0_f32, 1.0, 1.0, 2.0, 3.0]);
+            let edge = (0..n_nodes, 0..n_nodes, 0..3_usize, weight)
+                .prop_map(|(u, v, layer, w)| (u, v, layer, layer, 0, w));
+            (Just(n_nodes), prop::collection::vec(edge, 0..4 * n_nodes))
+        })
+    }
+
+    /// The Pareto set of the walks of at most `n_steps` edges, by brute force.
+    fn pareto_distances_in_n_steps(
+        graph: &impl MultidistanceGraph,
+        source: NodeID,
+        target: NodeID,
+        n_steps: usize,
+    ) -> Vec<MultiDistance> {
+        let mut walks = HashMap::from([(source, vec![MultiDistance::default()])]);
+        let mut to_target = Vec::new();
+        for _ in 0..n_steps {
+            let mut next: HashMap<NodeID, Vec<MultiDistance>> = HashMap::new();
+            for (node, dists) in &walks {
+                for (child, edge) in graph.neighbor_edges(node) {
+                    let child_dists = next.entry(child).or_default();
+                    child_dists.extend(dists.iter().map(|d| d.clone() + edge.clone()));
+                }
+            }
+            for dists in next.values_mut() {
+                *dists = multimin(dists);
+            }
+            to_target.extend(next.get(&target).into_iter().flatten().cloned());
+            walks = next;
+        }
+        multimin(&to_target)
+    }
+
+    fn same_set(a: &[MultiDistance], b: &[MultiDistance]) -> bool {
+        a.len() == b.len() && a.iter().all(|d| b.contains(d))
+    }
+
+    /// Compares the bidirectional search between every pair of nodes with a
+    /// forward search, or a brute-force one if the depth is bounded.
+    fn check_bidirectional(
+        graph: &impl MultidistanceGraph,
+        n_nodes: usize,
+        max_depth: Option<usize>,
+    ) -> Result<(), TestCaseError> {
+        for source in (0..n_nodes).map(NodeID) {
+            for target in (0..n_nodes).map(NodeID).filter(|t| *t != source) {
+                let dists = pareto_distances_bidirectional(graph, source, target, max_depth, None);
+                let expected = match max_depth {
+                    Some(n_steps) => pareto_distances_in_n_steps(graph, source, target, n_steps),
+                    None => pareto_distances_between(graph, source, target),
+                };
+                prop_assert!(same_set(&dists, &expected), "{:?} != {:?}", dists, expected);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_bidirectional_follows_edge_direction() {
+        let edges = [(0, 1, 0, 0, 0, 1.0), (1, 2, 0, 0, 0, 1.0)];
+        let directed = MultidistanceGraphHashmap::from_tuple_edge_list(&edges);
+        let undirected = UndirectedMultidistanceGraphHashmap::from_tuple_edge_list(&edges);
+        let two_steps = vec![MultiDistance::from_tuple(0, 0, 0, 2.0)];
+
+        assert_eq!(
+            pareto_distances_bidirectional(&directed, NodeID(0), NodeID(2), None, None),
+            two_steps
+        );
+        assert!(
+            pareto_distances_bidirectional(&directed, NodeID(2), NodeID(0), None, None).is_empty()
+        );
+        assert_eq!(
+            pareto_distances_bidirectional(&undirected, NodeID(2), NodeID(0), None, None),
+            two_steps
+        );
+        assert!(
+            pareto_distances_bidirectional(&undirected, NodeID(2), NodeID(0), Some(1), None)
+                .is_empty()
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn test_bidirectional_matches_forward_search((n_nodes, edges) in edge_list()) {
+            let directed = MultidistanceGraphHashmap::from_tuple_edge_list(&edges);
+            check_bidirectional(&directed, n_nodes, None)?;
+            let undirected = UndirectedMultidistanceGraphHashmap::from_tuple_edge_list(&edges);
+            check_bidirectional(&undirected, n_nodes, None)?;
+        }
+
+        #[test]
+        fn test_bidirectional_respects_max_depth(
+            (n_nodes, edges) in edge_list(),
+            n_steps in 0..4_usize,
+        ) {
+            let directed = MultidistanceGraphHashmap::from_tuple_edge_list(&edges);
+            check_bidirectional(&directed, n_nodes, Some(n_steps))?;
+            let undirected = UndirectedMultidistanceGraphHashmap::from_tuple_edge_list(&edges);
+            check_bidirectional(&undirected, n_nodes, Some(n_steps))?;
+        }
+
+        #[test]
+        fn test_pareto_distances_between_matches_single_source((n_nodes, edges) in edge_list()) {
+            let graph = MultidistanceGraphHashmap::from_tuple_edge_list(&edges);
+            for source in (0..n_nodes).map(NodeID) {
+                let all = parteto_shortest_distance_from_source(source, &graph, None, None);
+                for target in (0..n_nodes).map(NodeID).filter(|t| *t != source) {
+                    let dists = pareto_distances_between(&graph, source, target);
+                    let expected = all.get(&target).cloned().unwrap_or_default();
+                    prop_assert_eq!(dists.len(), expected.len());
+                    prop_assert!(dists.iter().all(|d| expected.contains(d)));
+                }
+            }
+        }
+    }
 }